@@ -0,0 +1,171 @@
+//! Client for the aktivitetstillfällen API, that lists the exams (and other
+//! activities) registered in Ladok and the students in them.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use akt_api::AktApi;
+//! use chrono::NaiveDate;
+//! use std::time::Duration;
+//!
+//! let akt = AktApi::new("https://api.kth.se/api/aktivitetstillfallen", "token")
+//!     .unwrap()
+//!     .timeout(Duration::from_secs(600));
+//! let from = NaiveDate::from_ymd(2021, 3, 15);
+//! let to = NaiveDate::from_ymd(2021, 3, 19);
+//!
+//! for activity in akt.activities(from, to).unwrap() {
+//!     println!("{} {:?}", activity.ladok_uid, activity.course_codes);
+//! }
+//! ```
+use api_client::ApiClient;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::time::Duration;
+
+pub use api_client::ApiError;
+
+/// Default timeout of the requests. The API needs a long time to respond for
+/// ranges of several days
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Client for the aktivitetstillfällen API
+#[derive(Clone, Debug)]
+pub struct AktApi {
+    client: ApiClient,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Response {
+    pub aktivitetstillfallen: Vec<Aktivitetstillfalle>,
+}
+
+/// An activity (aktivitetstillfälle), like an exam
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Aktivitetstillfalle {
+    #[serde(rename = "ladokUID")]
+    pub ladok_uid: String,
+
+    /// Day of the activity
+    pub date: Option<NaiveDate>,
+
+    /// Name of the activity, like "TEN1"
+    pub name: Option<String>,
+
+    /// Type of activity, like "Skriftlig tentamen"
+    pub activity_type: Option<String>,
+
+    /// Courses examined in the activity. Usually one
+    #[serde(default)]
+    pub course_codes: Vec<String>,
+
+    /// Students registered in the activity
+    #[serde(default)]
+    pub students: Vec<Student>,
+}
+
+/// A student registered in an activity
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Student {
+    #[serde(rename = "ladokUID")]
+    pub ladok_uid: String,
+
+    /// KTH ID, like "u1abcdef"
+    pub kth_id: Option<String>,
+
+    /// FUNKA codes (adaptations) the student has in the activity
+    #[serde(default)]
+    pub funka: Vec<String>,
+}
+
+impl AktApi {
+    /// Creates a client for the API in `url`. The `token` is sent in every
+    /// request
+    pub fn new(url: &str, token: &str) -> Result<AktApi, ApiError> {
+        let client = ApiClient::new(url)?
+            .timeout(DEFAULT_TIMEOUT)
+            .header("canvas_api_token", token);
+
+        Ok(AktApi { client })
+    }
+
+    /// Sets the timeout of the requests. Default: [`DEFAULT_TIMEOUT`]
+    pub fn timeout(mut self, timeout: Duration) -> AktApi {
+        self.client = self.client.timeout(timeout);
+        self
+    }
+
+    /// Get all the activities between two dates (both included), in one
+    /// request
+    pub fn activities(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<Aktivitetstillfalle>, ApiError> {
+        let from = from.to_string();
+        let to = to.to_string();
+
+        let response: Response = self.client.get_json(
+            "aktivitetstillfallen/students",
+            &[("fromDate", &from), ("toDate", &to)],
+        )?;
+
+        Ok(response.aktivitetstillfallen)
+    }
+
+    /// Get the activities in a given date
+    pub fn activities_on(&self, date: NaiveDate) -> Result<Vec<Aktivitetstillfalle>, ApiError> {
+        self.activities(date, date)
+    }
+}
+
+impl Aktivitetstillfalle {
+    /// Returns the SIS ID of the exam room in Canvas
+    pub fn sis_id(&self) -> String {
+        format!("AKT.{}", self.ladok_uid)
+    }
+
+    /// Returns the SIS ID of the exam room for students with FUNKA
+    /// adaptations
+    pub fn funka_sis_id(&self) -> String {
+        format!("AKT.{}.FUNKA", self.ladok_uid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_activities() {
+        let response: Response = serde_json::from_str(
+            r#"{
+                "aktivitetstillfallen": [
+                    {
+                        "ladokUID": "abc-123",
+                        "date": "2021-03-15",
+                        "name": "TEN1",
+                        "activityType": "Skriftlig tentamen",
+                        "courseCodes": ["SF1624", "SF1625"],
+                        "students": [
+                            { "ladokUID": "s-1", "kthId": "u1abcdef", "funka": ["R"] },
+                            { "ladokUID": "s-2" }
+                        ]
+                    },
+                    { "ladokUID": "def-456" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let activities = response.aktivitetstillfallen;
+        assert_eq!(activities[0].date, Some(NaiveDate::from_ymd(2021, 3, 15)));
+        assert_eq!(activities[0].course_codes, vec!["SF1624", "SF1625"]);
+        assert_eq!(activities[0].students[0].funka, vec!["R"]);
+        assert_eq!(activities[0].students[1].kth_id, None);
+        assert_eq!(activities[0].funka_sis_id(), "AKT.abc-123.FUNKA");
+        assert!(activities[1].students.is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Small HTTP client shared by the clients of KTH APIs (Kopps,
+//! aktivitetstillfällen...)
+//!
+//! It takes care of the things every one of them needs:
+//!
+//! - Base URLs are normalized, so `https://api.kth.se/api/kopps/v2` and
+//!   `https://api.kth.se/api/kopps/v2/` work the same
+//! - Requests have a timeout
+//! - Requests that fail because of the network, a timeout or a server error
+//!   (5xx or 429) are retried
+//! - Errors are typed and say which URL failed
+//!
+//! Example:
+//!
+//! ```no_run
+//! use api_client::ApiClient;
+//! use std::time::Duration;
+//!
+//! let client = ApiClient::new("https://api.kth.se/api/kopps/v2")
+//!     .unwrap()
+//!     .timeout(Duration::from_secs(60));
+//! let schools: serde_json::Value = client.get_json("schools", &[]).unwrap();
+//! ```
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+/// Client for an API under a base URL.
+///
+/// The `Debug` output does not contain the values of the headers, since they
+/// are often tokens.
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: Url,
+    http: Client,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+/// Errors that can happen when calling an API
+#[derive(Debug)]
+pub enum ApiError {
+    /// The base URL or the path is not valid
+    Url(String, url::ParseError),
+
+    /// The request could not be performed, even after retrying
+    Http(String, reqwest::Error),
+
+    /// The API responded with an error status
+    Status(String, u16, String),
+
+    /// The response is not what was expected
+    Json(String, reqwest::Error),
+}
+
+impl fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|(name, _)| format!("{}: <redacted>", name))
+            .collect();
+
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url.as_str())
+            .field("headers", &headers)
+            .field("timeout", &self.timeout)
+            .field("max_retries", &self.max_retries)
+            .field("retry_delay", &self.retry_delay)
+            .finish()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Url(url, e) => write!(f, "Invalid URL `{}`: {}", url, e),
+            ApiError::Http(url, e) => write!(f, "Request to {} failed: {}", url, e),
+            ApiError::Status(url, status, body) => {
+                write!(f, "{} responded {}: {}", url, status, body)
+            }
+            ApiError::Json(url, e) => write!(f, "Cannot read the response of {}: {}", url, e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Url(_, e) => Some(e),
+            ApiError::Http(_, e) | ApiError::Json(_, e) => Some(e),
+            ApiError::Status(..) => None,
+        }
+    }
+}
+
+impl ApiClient {
+    /// Creates a client for the API in `base_url`. A trailing slash is
+    /// added if missing, so paths are always relative to the whole URL.
+    pub fn new(base_url: &str) -> Result<ApiClient, ApiError> {
+        Ok(ApiClient {
+            base_url: normalize(base_url)?,
+            http: Client::new(),
+            headers: vec![],
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+        })
+    }
+
+    /// Adds a header to every request, like a token
+    pub fn header(mut self, name: &str, value: &str) -> ApiClient {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the timeout of every request. Default: 30 seconds
+    pub fn timeout(mut self, timeout: Duration) -> ApiClient {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a failed request is retried. Default: 3
+    pub fn max_retries(mut self, max_retries: u32) -> ApiClient {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the time to wait before the first retry. It doubles with every
+    /// retry. Default: 1 second
+    pub fn retry_delay(mut self, retry_delay: Duration) -> ApiClient {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Returns the URL of a path (relative to the base URL) with query
+    /// parameters
+    pub fn url(&self, path: &str, query: &[(&str, &str)]) -> Result<Url, ApiError> {
+        let mut url = self
+            .base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|e| ApiError::Url(path.to_string(), e))?;
+
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        Ok(url)
+    }
+
+    /// Performs a GET request and returns the response if its status is
+    /// successful
+    pub fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Response, ApiError> {
+        let url = self.url(path, query)?;
+        self.send(url.as_str(), || self.http.get(url.clone()))
+    }
+
+    /// Performs a GET request and parses the JSON response
+    pub fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, ApiError> {
+        let response = self.get(path, query)?;
+        let url = response.url().to_string();
+
+        response.json().map_err(|e| ApiError::Json(url, e))
+    }
+
+    /// Sends a request, retrying it if needed. `request` builds it again for
+    /// every attempt
+    fn send<F: Fn() -> RequestBuilder>(&self, url: &str, request: F) -> Result<Response, ApiError> {
+        let mut attempt = 0;
+
+        loop {
+            let mut builder = request().timeout(self.timeout);
+
+            for (name, value) in &self.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+
+            let result = builder.send();
+            let retry = match &result {
+                Ok(response) => is_transient(response.status()),
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+
+            if retry && attempt < self.max_retries {
+                thread::sleep(self.retry_delay * 2u32.pow(attempt));
+                attempt += 1;
+                continue;
+            }
+
+            let response = result.map_err(|e| ApiError::Http(url.to_string(), e))?;
+            let status = response.status();
+
+            if !status.is_success() {
+                let body = response.text().unwrap_or_default();
+                return Err(ApiError::Status(url.to_string(), status.as_u16(), body));
+            }
+
+            return Ok(response);
+        }
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Parses a base URL and adds the trailing slash
+fn normalize(base_url: &str) -> Result<Url, ApiError> {
+    let with_slash = match base_url.ends_with('/') {
+        true => base_url.to_string(),
+        false => format!("{}/", base_url),
+    };
+
+    Url::parse(&with_slash).map_err(|e| ApiError::Url(base_url.to_string(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn normalizes_base_urls() {
+        for base in &[
+            "https://api.kth.se/api/kopps/v2",
+            "https://api.kth.se/api/kopps/v2/",
+        ] {
+            let client = ApiClient::new(base).unwrap();
+
+            assert_eq!(
+                client
+                    .url("/courses/offerings", &[("from", "20201")])
+                    .unwrap()
+                    .as_str(),
+                "https://api.kth.se/api/kopps/v2/courses/offerings?from=20201"
+            );
+        }
+
+        assert!(matches!(
+            ApiClient::new("not a url"),
+            Err(ApiError::Url(..))
+        ));
+    }
+
+    /// Starts a server that responds 503 to the first `failures` requests
+    /// and then 200. Returns its URL and the number of requests received
+    fn flaky_server(failures: u32) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let (status, body) = match counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    true => ("503 Service Unavailable", "{}"),
+                    false => ("200 OK", r#"{"ok":true}"#),
+                };
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn debug_hides_the_headers() {
+        let client = ApiClient::new("https://api.kth.se/api/akt/v1")
+            .unwrap()
+            .header("canvas_api_token", "secret");
+        let debug = format!("{:?}", client);
+
+        assert!(debug.contains("canvas_api_token"));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, requests) = flaky_server(2);
+        let client = ApiClient::new(&url)
+            .unwrap()
+            .retry_delay(Duration::from_millis(1));
+
+        let response: serde_json::Value = client.get_json("status", &[]).unwrap();
+
+        assert_eq!(response["ok"], true);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let (url, requests) = flaky_server(10);
+        let client = ApiClient::new(&url)
+            .unwrap()
+            .max_retries(1)
+            .retry_delay(Duration::from_millis(1));
+
+        match client.get("status", &[]) {
+            Err(ApiError::Status(_, 503, _)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
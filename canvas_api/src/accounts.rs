@@ -0,0 +1,343 @@
+//! Accounts and the tree of sub-accounts
+//!
+//! KTH has a root account with one sub-account per school, and those have
+//! sub-accounts for departments and so on. [`account_tree`] fetches the whole
+//! tree under an account so reports can walk it.
+//!
+//! Example: count courses per school
+//!
+//! ```no_run
+//! use canvas_api::accounts::{self, TreeOptions};
+//! use canvas_api::courses::CanvasId;
+//! use canvas_api::CanvasApi;
+//!
+//! let api = CanvasApi::new("https://kth.test.instructure.com/api/v1", "XXXX");
+//! let options = TreeOptions { course_counts: true };
+//! let tree = accounts::account_tree(&api, &CanvasId::Id(1), &options).unwrap();
+//!
+//! for school in tree.children(tree.root()) {
+//!     println!("{}: {}", school.account.name, tree.total_course_count(school));
+//! }
+//! ```
+use crate::courses::CanvasId;
+use crate::CanvasApi;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+
+/// An account as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Account {
+    pub id: u64,
+    pub name: String,
+    pub parent_account_id: Option<u64>,
+    pub root_account_id: Option<u64>,
+    pub sis_account_id: Option<String>,
+
+    /// Number of courses directly in the account. Only present if requested
+    pub course_count: Option<u64>,
+}
+
+/// Options for [`account_tree`]
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Request the number of courses in every sub-account
+    pub course_counts: bool,
+}
+
+/// A tree of accounts. Nodes are stored in a vector and refer to their
+/// parent and children by index.
+#[derive(Debug, Clone)]
+pub struct AccountTree {
+    nodes: Vec<AccountNode>,
+}
+
+/// An account in an [`AccountTree`]
+#[derive(Debug, Clone)]
+pub struct AccountNode {
+    pub account: Account,
+
+    /// Index of the parent node. `None` for the root
+    pub parent: Option<usize>,
+
+    /// Indices of the children nodes
+    pub children: Vec<usize>,
+
+    /// Distance to the root. The root has depth 0
+    pub depth: usize,
+}
+
+impl AccountTree {
+    /// Builds a tree from a root and a list of accounts under it, in any
+    /// order. Accounts whose parent is not in the list are ignored.
+    pub fn from_accounts(root: Account, accounts: Vec<Account>) -> AccountTree {
+        let mut by_parent: HashMap<u64, Vec<Account>> = HashMap::new();
+
+        for account in accounts {
+            if let Some(parent) = account.parent_account_id {
+                by_parent.entry(parent).or_default().push(account);
+            }
+        }
+
+        let mut tree = AccountTree {
+            nodes: vec![AccountNode {
+                account: root,
+                parent: None,
+                children: vec![],
+                depth: 0,
+            }],
+        };
+        let mut queue = VecDeque::from(vec![0]);
+
+        while let Some(index) = queue.pop_front() {
+            let id = tree.nodes[index].account.id;
+            let mut children = by_parent.remove(&id).unwrap_or_default();
+            children.sort_by(|a, b| a.name.cmp(&b.name));
+
+            for child in children {
+                let child_index = tree.nodes.len();
+                tree.nodes.push(AccountNode {
+                    account: child,
+                    parent: Some(index),
+                    children: vec![],
+                    depth: tree.nodes[index].depth + 1,
+                });
+                tree.nodes[index].children.push(child_index);
+                queue.push_back(child_index);
+            }
+        }
+
+        tree
+    }
+
+    pub fn root(&self) -> &AccountNode {
+        &self.nodes[0]
+    }
+
+    pub fn get(&self, index: usize) -> Option<&AccountNode> {
+        self.nodes.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Finds the node of an account
+    pub fn find(&self, account_id: u64) -> Option<&AccountNode> {
+        self.nodes.iter().find(|n| n.account.id == account_id)
+    }
+
+    pub fn parent(&self, node: &AccountNode) -> Option<&AccountNode> {
+        node.parent.map(|i| &self.nodes[i])
+    }
+
+    pub fn children<'t>(&'t self, node: &'t AccountNode) -> impl Iterator<Item = &'t AccountNode> {
+        node.children.iter().map(move |i| &self.nodes[*i])
+    }
+
+    /// Returns the accounts from the root to `node`, both included
+    pub fn path<'t>(&'t self, node: &'t AccountNode) -> Vec<&'t Account> {
+        let mut path = vec![&node.account];
+        let mut current = node;
+
+        while let Some(parent) = self.parent(current) {
+            path.push(&parent.account);
+            current = parent;
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Iterates through all the nodes, parents before their children
+    pub fn iter(&self) -> impl Iterator<Item = &AccountNode> {
+        let mut stack = vec![0];
+
+        std::iter::from_fn(move || {
+            let index = stack.pop()?;
+            let node = &self.nodes[index];
+            stack.extend(node.children.iter().rev());
+
+            Some(node)
+        })
+    }
+
+    /// Returns the IDs of all the accounts, in the order of
+    /// [`AccountTree::iter`].
+    ///
+    /// Do not use them to list courses: [`courses::list_courses`] already
+    /// includes the courses in sub-accounts, so listing every account gives
+    /// repeated courses.
+    ///
+    /// [`courses::list_courses`]: crate::courses::list_courses
+    pub fn account_ids(&self) -> Vec<u64> {
+        self.iter().map(|node| node.account.id).collect()
+    }
+
+    /// Returns the number of courses in `node` and all its descendants.
+    /// Accounts without `course_count` (like the root, or all of them if the
+    /// counts were not requested) count as zero.
+    pub fn total_course_count(&self, node: &AccountNode) -> u64 {
+        node.account.course_count.unwrap_or(0)
+            + self
+                .children(node)
+                .map(|child| self.total_course_count(child))
+                .sum::<u64>()
+    }
+}
+
+/// Gets an account
+pub fn get_account(api: &CanvasApi, account: &CanvasId) -> Result<Account, crate::Error> {
+    Ok(api
+        .get(&format!("/accounts/{}", account.account()))?
+        .error_for_status()?
+        .json()?)
+}
+
+/// Gets the sub-accounts of an account. With `recursive`, returns all the
+/// accounts under it, not only the direct children.
+pub fn sub_accounts(
+    api: &CanvasApi,
+    account_id: u64,
+    recursive: bool,
+    options: &TreeOptions,
+) -> Result<Vec<Account>, crate::Error> {
+    let mut endpoint = format!("/accounts/{}/sub_accounts?per_page=100", account_id);
+
+    if recursive {
+        endpoint.push_str("&recursive=true");
+    }
+
+    if options.course_counts {
+        endpoint.push_str("&include[]=course_count");
+    }
+
+    let mut accounts = vec![];
+
+    for page in api.get_paginated(&endpoint) {
+        accounts.append(&mut page?.error_for_status()?.json::<Vec<Account>>()?);
+    }
+
+    Ok(accounts)
+}
+
+/// Fetches the tree of accounts under `root`.
+///
+/// It first asks Canvas for all the sub-accounts at once (`recursive=true`).
+/// Canvas may time out or fail with a server error in big trees: only in
+/// that case, it falls back to requesting the sub-accounts of every account,
+/// level by level. Other errors (like "401 Unauthorized") are returned.
+///
+/// The root account does not get `course_count` since Canvas does not return
+/// it in this endpoint.
+pub fn account_tree(
+    api: &CanvasApi,
+    root: &CanvasId,
+    options: &TreeOptions,
+) -> Result<AccountTree, crate::Error> {
+    let root = get_account(api, root)?;
+
+    let accounts = match sub_accounts(api, root.id, true, options) {
+        Ok(accounts) => accounts,
+        Err(e) if is_unavailable(&e) => sub_accounts_by_level(api, root.id, options)?,
+        Err(e) => return Err(e),
+    };
+
+    Ok(AccountTree::from_accounts(root, accounts))
+}
+
+/// Returns `true` if the error means that Canvas could not answer the
+/// request (timeouts and server errors), not that the request is wrong
+fn is_unavailable(error: &crate::Error) -> bool {
+    match error {
+        crate::Error::Http(e) => e.is_timeout() || e.status().is_some_and(|s| s.is_server_error()),
+        crate::Error::Token(_) => false,
+    }
+}
+
+fn sub_accounts_by_level(
+    api: &CanvasApi,
+    root_id: u64,
+    options: &TreeOptions,
+) -> Result<Vec<Account>, crate::Error> {
+    let mut accounts = vec![];
+    let mut pending = VecDeque::from(vec![root_id]);
+
+    while let Some(id) = pending.pop_front() {
+        let children = sub_accounts(api, id, false, options)?;
+        pending.extend(children.iter().map(|a| a.id));
+        accounts.extend(children);
+    }
+
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(id: u64, name: &str, parent: Option<u64>, courses: u64) -> Account {
+        Account {
+            id,
+            name: name.to_string(),
+            parent_account_id: parent,
+            root_account_id: Some(1),
+            sis_account_id: None,
+            course_count: Some(courses),
+        }
+    }
+
+    #[test]
+    fn falls_back_only_when_unavailable() {
+        let error = |status: u16| {
+            let response: reqwest::blocking::Response = http::Response::builder()
+                .status(status)
+                .body("")
+                .unwrap()
+                .into();
+            crate::Error::Http(response.error_for_status().unwrap_err())
+        };
+
+        assert!(is_unavailable(&error(503)));
+        assert!(!is_unavailable(&error(401)));
+        assert!(!is_unavailable(&error(403)));
+    }
+
+    #[test]
+    fn builds_the_tree() {
+        let tree = AccountTree::from_accounts(
+            account(1, "KTH", None, 0),
+            vec![
+                account(12, "Department B", Some(2), 3),
+                account(3, "SCI", Some(1), 1),
+                account(2, "EECS", Some(1), 2),
+                account(11, "Department A", Some(2), 4),
+                account(99, "Orphan", Some(98), 100),
+            ],
+        );
+
+        let names: Vec<&str> = tree.iter().map(|n| n.account.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["KTH", "EECS", "Department A", "Department B", "SCI"]
+        );
+
+        let department = tree.find(12).unwrap();
+        assert_eq!(department.depth, 2);
+        assert_eq!(tree.parent(department).unwrap().account.name, "EECS");
+        assert_eq!(
+            tree.path(department)
+                .iter()
+                .map(|a| a.id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 12]
+        );
+
+        assert_eq!(tree.total_course_count(tree.find(2).unwrap()), 9);
+        assert_eq!(tree.total_course_count(tree.root()), 10);
+        assert_eq!(tree.account_ids(), vec![1, 2, 11, 12, 3]);
+    }
+}
@@ -0,0 +1,264 @@
+//! On-disk cache for GET responses
+//!
+//! Every cached response is stored as a JSON file in a directory. Files are
+//! named after a hash of the requested URL so the same URL always hits the
+//! same file. The access token is sent in a header and never is part of the
+//! key.
+use reqwest::blocking::Response;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ETAG, LAST_MODIFIED};
+use reqwest::{ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Headers that are stored together with the body. `link` is needed to
+/// continue paginated requests from a cached page.
+const STORED_HEADERS: [&str; 4] = ["content-type", "link", "etag", "last-modified"];
+
+/// Cache for GET responses stored in a directory. Responses younger than
+/// `ttl` are returned without contacting Canvas. Older responses are
+/// revalidated with conditional requests (`If-None-Match` and
+/// `If-Modified-Since`) when Canvas gave an `ETag` or `Last-Modified` header.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    directory: PathBuf,
+    ttl: Duration,
+}
+
+/// A response as it is stored in disk
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct CachedResponse {
+    url: String,
+    stored_at: u64,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl ResponseCache {
+    /// Creates a cache that stores responses in `directory`. The directory is
+    /// created when the first response is stored.
+    pub fn new<P: Into<PathBuf>>(directory: P, ttl: Duration) -> ResponseCache {
+        ResponseCache {
+            directory: directory.into(),
+            ttl,
+        }
+    }
+
+    /// Removes every cached response
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.directory) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    pub(crate) fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the cached response for a URL, fresh or not
+    pub(crate) fn load(&self, url: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.path(url)).ok()?;
+        let cached = serde_json::from_str::<CachedResponse>(&content).ok()?;
+
+        // Guard against hash collisions
+        match cached.url == cache_key(url) {
+            true => Some(cached),
+            false => None,
+        }
+    }
+
+    /// Stores a response. Failing to write is not an error: the response is
+    /// just not cached.
+    pub(crate) fn store(&self, cached: &CachedResponse) {
+        if fs::create_dir_all(&self.directory).is_err() {
+            return;
+        }
+
+        if let Ok(content) = serde_json::to_string(cached) {
+            fs::write(self.path(&cached.url), content).ok();
+        }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.json", fnv1a(cache_key(url).as_bytes())))
+    }
+}
+
+impl CachedResponse {
+    /// Reads the whole body of `response` and turns it into a cacheable
+    /// response.
+    pub(crate) fn from_response(
+        url: &str,
+        response: Response,
+    ) -> Result<CachedResponse, reqwest::Error> {
+        let status = response.status().as_u16();
+        let headers = STORED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                response
+                    .headers()
+                    .get(*name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| (name.to_string(), v.to_string()))
+            })
+            .collect();
+        let body = response.text()?;
+
+        Ok(CachedResponse {
+            url: cache_key(url),
+            stored_at: now(),
+            status,
+            headers,
+            body,
+        })
+    }
+
+    pub(crate) fn is_fresh(&self, ttl: Duration) -> bool {
+        now().saturating_sub(self.stored_at) < ttl.as_secs()
+    }
+
+    /// Marks the response as just validated against Canvas
+    pub(crate) fn touch(&mut self) {
+        self.stored_at = now();
+    }
+
+    /// Returns the headers to send in a conditional request to revalidate
+    /// this response
+    pub(crate) fn conditional_headers(&self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+
+        for (name, value) in &self.headers {
+            let conditional = match name.as_str() {
+                "etag" => reqwest::header::IF_NONE_MATCH,
+                "last-modified" => reqwest::header::IF_MODIFIED_SINCE,
+                _ => continue,
+            };
+
+            if let Ok(value) = HeaderValue::from_str(value) {
+                map.insert(conditional, value);
+            }
+        }
+
+        map
+    }
+
+    pub(crate) fn can_revalidate(&self) -> bool {
+        self.headers
+            .iter()
+            .any(|(name, _)| name == ETAG.as_str() || name == LAST_MODIFIED.as_str())
+    }
+
+    pub(crate) fn to_response(&self, url: &str) -> Response {
+        let mut builder = http::Response::builder().status(self.status);
+
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                builder = builder.header(name, value);
+            }
+        }
+
+        if let Ok(url) = Url::parse(url) {
+            builder = builder.url(url);
+        }
+
+        builder
+            .body(self.body.clone())
+            .expect("Error when building a cached response")
+            .into()
+    }
+}
+
+/// Returns the URL used as key in the cache: the URL without any
+/// `access_token` query parameter
+fn cache_key(url: &str) -> String {
+    let mut parsed = match Url::parse(url) {
+        Err(_) => return url.to_string(),
+        Ok(parsed) => parsed,
+    };
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| key != "access_token")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    parsed.to_string()
+}
+
+/// 64-bit FNV-1a hash. Used instead of `DefaultHasher` because its output
+/// must be the same across Rust versions to find files from previous runs.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_excludes_token() {
+        assert_eq!(
+            cache_key("https://canvas.test/api/v1/courses?access_token=X&per_page=100"),
+            "https://canvas.test/api/v1/courses?per_page=100"
+        );
+        assert_eq!(
+            cache_key("https://canvas.test/api/v1/courses?access_token=X"),
+            "https://canvas.test/api/v1/courses"
+        );
+    }
+
+    #[test]
+    fn stores_and_loads_responses() {
+        let directory = std::env::temp_dir().join(format!("canvas-cache-{}", std::process::id()));
+        let cache = ResponseCache::new(&directory, Duration::from_secs(60));
+        let url = "https://canvas.test/api/v1/courses/1";
+
+        assert!(cache.load(url).is_none());
+
+        cache.store(&CachedResponse {
+            url: cache_key(url),
+            stored_at: now(),
+            status: 200,
+            headers: vec![("etag".to_string(), "\"abc\"".to_string())],
+            body: "{\"id\":1}".to_string(),
+        });
+
+        let cached = cache.load(url).unwrap();
+        assert!(cached.is_fresh(cache.ttl()));
+        assert!(cached.can_revalidate());
+        assert_eq!(
+            cached.conditional_headers()[reqwest::header::IF_NONE_MATCH],
+            "\"abc\""
+        );
+
+        let response = cached.to_response(url);
+        assert_eq!(response.headers()[ETAG], "\"abc\"");
+        assert_eq!(response.text().unwrap(), "{\"id\":1}");
+
+        cache.clear().unwrap();
+        assert!(cache.load(url).is_none());
+    }
+}
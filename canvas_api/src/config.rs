@@ -0,0 +1,373 @@
+//! Configuration file with named Canvas profiles
+//!
+//! A profile contains everything needed to talk to one Canvas environment.
+//! Profiles are written in a TOML file like this one:
+//!
+//! ```toml
+//! default_profile = "test"
+//!
+//! [profiles.prod]
+//! url = "https://kth.instructure.com/api/v1"
+//! token_command = "pass show canvas/prod"
+//! account_id = 1
+//!
+//! [profiles.beta]
+//! url = "https://kth.beta.instructure.com/api/v1"
+//! token_file = "/home/me/.canvas-beta-token"
+//!
+//! [profiles.stage]
+//! url = "https://kth.test.instructure.com/api/v1"
+//! token_keyring = { service = "canvas", account = "test" }
+//!
+//! [profiles.shared]
+//! url = "https://kth.instructure.com/api/v1"
+//!
+//! [profiles.shared.oauth]
+//! client_id = "10000000000001"
+//! client_secret = "XXXX"
+//! redirect_uri = "http://localhost:8765/callback"
+//! token_store = "/home/me/.canvas-oauth-shared.json"
+//!
+//! [profiles.test]
+//! url = "https://kth.test.instructure.com/api/v1"
+//! token = "XXXX"
+//! account_id = 1
+//!
+//! [profiles.test.rate_limit]
+//! requests_per_second = 10.0
+//! max_retries = 5
+//! ```
+//!
+//! The token is read from the first of `token`, `token_command`, `token_file`
+//! and `token_keyring` that is set. See [`TokenSource`] to learn more. Profiles
+//! with an `oauth` section use the OAuth2 authorization code flow instead.
+//! See [`OAuthSession`].
+//!
+//! Apps choose the profile with a `--profile <name>` argument or the
+//! `CANVAS_PROFILE` environmental variable. See [`selected_profile`].
+use crate::oauth::{OAuthClient, OAuthError, OAuthSession};
+use crate::token::{KeyringEntry, TokenError};
+use crate::{CanvasApi, RateLimit, Token, TokenSource};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the environmental variable with the path to the configuration file
+pub const CONFIG_ENV: &str = "CANVAS_CONFIG";
+
+/// Name of the environmental variable with the name of the profile to use
+pub const PROFILE_ENV: &str = "CANVAS_PROFILE";
+
+/// Path to the configuration file used when `CANVAS_CONFIG` is not set
+pub const DEFAULT_CONFIG_PATH: &str = "canvas.toml";
+
+/// Contents of the configuration file
+#[derive(Deserialize, Debug)]
+pub struct Config {
+    /// Profile used when none is chosen explicitly
+    pub default_profile: Option<String>,
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Settings for one Canvas environment
+#[derive(Deserialize, Debug, Clone)]
+pub struct Profile {
+    /// Name of the profile in the configuration file
+    #[serde(skip)]
+    pub name: String,
+
+    /// URL to the Canvas API, including `/api/v1`
+    pub url: String,
+
+    /// Access token written directly in the file
+    pub token: Option<Token>,
+
+    /// Shell command that prints the access token
+    pub token_command: Option<String>,
+
+    /// File with the access token
+    pub token_file: Option<PathBuf>,
+
+    /// Entry in the OS keyring with the access token
+    pub token_keyring: Option<KeyringEntry>,
+
+    /// Developer key to use the OAuth2 flow instead of a token
+    pub oauth: Option<OAuthSettings>,
+
+    /// ID of the root account
+    pub account_id: Option<u64>,
+
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+}
+
+/// Developer key and the file where the OAuth2 tokens are stored
+#[derive(Deserialize, Debug, Clone)]
+pub struct OAuthSettings {
+    pub client_id: String,
+    pub client_secret: Token,
+    pub redirect_uri: String,
+    pub token_store: PathBuf,
+}
+
+/// Errors that can happen while loading a configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read
+    Io(PathBuf, io::Error),
+
+    /// The configuration file is not valid
+    Parse(PathBuf, toml::de::Error),
+
+    /// There is no profile with the given name
+    ProfileNotFound(String),
+
+    /// No profile was chosen and there is no default profile
+    NoProfileSelected,
+
+    /// The profile has no way of obtaining a token
+    NoToken(String),
+
+    /// The token could not be obtained
+    Token(String, TokenError),
+
+    /// The OAuth2 flow failed
+    OAuth(String, OAuthError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "Cannot read `{}`: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "Invalid file `{}`: {}", path.display(), e),
+            ConfigError::ProfileNotFound(name) => write!(f, "Profile `{}` not found", name),
+            ConfigError::NoProfileSelected => write!(
+                f,
+                "No profile selected. Use --profile, {} or `default_profile`",
+                PROFILE_ENV
+            ),
+            ConfigError::NoToken(name) => write!(f, "Profile `{}` has no token", name),
+            ConfigError::Token(name, e) => write!(f, "Profile `{}`: {}", name, e),
+            ConfigError::OAuth(name, e) => write!(f, "Profile `{}`: {}", name, e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads a configuration file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let content =
+            fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+
+        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Reads the configuration file in `CANVAS_CONFIG` or, if not set, in
+    /// `canvas.toml`
+    pub fn load_default() -> Result<Config, ConfigError> {
+        Config::load(config_path())
+    }
+
+    /// Returns the profile called `name`
+    pub fn profile(&self, name: &str) -> Result<Profile, ConfigError> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .map(|profile| Profile {
+                name: name.to_string(),
+                ..profile
+            })
+            .ok_or_else(|| ConfigError::ProfileNotFound(name.to_string()))
+    }
+
+    /// Returns the profile chosen by the user (see [`selected_profile`]) or
+    /// the default profile
+    pub fn selected(&self) -> Result<Profile, ConfigError> {
+        match selected_profile().or_else(|| self.default_profile.clone()) {
+            None => Err(ConfigError::NoProfileSelected),
+            Some(name) => self.profile(&name),
+        }
+    }
+}
+
+impl Profile {
+    /// Builds a profile from the environmental variables `CANVAS_API_URL` and
+    /// the ones read by [`TokenSource::from_env`]. Returns `None` if any of
+    /// them is missing.
+    pub fn from_env() -> Option<Profile> {
+        let url = std::env::var("CANVAS_API_URL").ok()?;
+        let mut profile = Profile {
+            name: "env".to_string(),
+            url,
+            token: None,
+            token_command: None,
+            token_file: None,
+            token_keyring: None,
+            oauth: None,
+            account_id: None,
+            rate_limit: RateLimit::default(),
+        };
+
+        match TokenSource::from_env()? {
+            TokenSource::Literal(token) => profile.token = Some(token),
+            TokenSource::Command(command) => profile.token_command = Some(command),
+            TokenSource::File(path) => profile.token_file = Some(path),
+            TokenSource::Keyring(entry) => profile.token_keyring = Some(entry),
+        }
+
+        Some(profile)
+    }
+
+    /// Returns where to obtain the token from
+    pub fn token_source(&self) -> Option<TokenSource> {
+        if let Some(token) = &self.token {
+            return Some(TokenSource::Literal(token.clone()));
+        }
+
+        if let Some(command) = &self.token_command {
+            return Some(TokenSource::Command(command.clone()));
+        }
+
+        if let Some(path) = &self.token_file {
+            return Some(TokenSource::File(path.clone()));
+        }
+
+        self.token_keyring.clone().map(TokenSource::Keyring)
+    }
+
+    /// Obtains the access token
+    pub fn token(&self) -> Result<Token, ConfigError> {
+        self.token_source()
+            .ok_or_else(|| ConfigError::NoToken(self.name.clone()))?
+            .resolve()
+            .map_err(|e| ConfigError::Token(self.name.clone(), e))
+    }
+
+    /// Obtains the token and creates a [`CanvasApi`] for this profile, with
+    /// its rate limit. Profiles with `oauth` settings open the stored OAuth2
+    /// session, asking the user to authorize the app if there is none.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use canvas_api::config::Config;
+    ///
+    /// let profile = Config::load_default().unwrap().selected().unwrap();
+    /// let api = profile.canvas_api().unwrap();
+    /// ```
+    pub fn canvas_api(&self) -> Result<CanvasApi<'_>, ConfigError> {
+        let api = match &self.oauth {
+            None => CanvasApi::with_token(&self.url, self.token()?),
+            Some(oauth) => {
+                let client = OAuthClient {
+                    canvas_url: self
+                        .url
+                        .trim_end_matches('/')
+                        .trim_end_matches("/api/v1")
+                        .to_string(),
+                    client_id: oauth.client_id.clone(),
+                    client_secret: oauth.client_secret.clone(),
+                    redirect_uri: oauth.redirect_uri.clone(),
+                };
+                let session = OAuthSession::open(client, &oauth.token_store)
+                    .map_err(|e| ConfigError::OAuth(self.name.clone(), e))?;
+
+                CanvasApi::with_provider(&self.url, session)
+            }
+        };
+
+        Ok(api.rate_limit(self.rate_limit.clone()))
+    }
+}
+
+/// Returns the name of the profile chosen by the user: the value of the
+/// `--profile` argument or, if missing, the `CANVAS_PROFILE` environmental
+/// variable.
+pub fn selected_profile() -> Option<String> {
+    profile_from_args(std::env::args()).or_else(|| std::env::var(PROFILE_ENV).ok())
+}
+
+/// Returns the path to the configuration file
+pub fn config_path() -> PathBuf {
+    std::env::var(CONFIG_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_CONFIG_PATH))
+}
+
+/// Finds the value of `--profile <name>` or `--profile=<name>` in a list of
+/// arguments
+pub fn profile_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        default_profile = "test"
+
+        [profiles.prod]
+        url = "https://kth.instructure.com/api/v1"
+        token_command = "echo ' secret '"
+
+        [profiles.test]
+        url = "https://kth.test.instructure.com/api/v1"
+        token = "XXXX"
+        account_id = 1
+
+        [profiles.test.rate_limit]
+        requests_per_second = 10.0
+    "#;
+
+    #[test]
+    fn reads_profiles() {
+        let config = toml::from_str::<Config>(EXAMPLE).unwrap();
+        let test = config.profile("test").unwrap();
+
+        assert_eq!(config.default_profile.as_deref(), Some("test"));
+        assert_eq!(test.account_id, Some(1));
+        assert_eq!(test.token().unwrap().expose(), "XXXX");
+        assert_eq!(test.rate_limit.requests_per_second, Some(10.0));
+        assert_eq!(test.rate_limit.max_retries, 3);
+        assert_eq!(
+            config.profile("prod").unwrap().token().unwrap().expose(),
+            "secret"
+        );
+        assert!(!format!("{:?}", test).contains("XXXX"));
+        assert!(config.profile("beta").is_err());
+    }
+
+    #[test]
+    fn finds_profile_in_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(profile_from_args(args(&["app"])), None);
+        assert_eq!(
+            profile_from_args(args(&["app", "--profile", "beta"])),
+            Some("beta".to_string())
+        );
+        assert_eq!(
+            profile_from_args(args(&["app", "--profile=prod", "x"])),
+            Some("prod".to_string())
+        );
+    }
+}
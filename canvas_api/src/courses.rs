@@ -0,0 +1,335 @@
+//! Lifecycle operations for courses and sections
+//!
+//! Create, update, publish and conclude courses, and create, update and
+//! cross-list sections. Every object can be referred to by its Canvas ID or
+//! by its SIS ID (see [`CanvasId`]).
+//!
+//! Operations that modify data return an [`Outcome`]: in dry-run mode,
+//! nothing is returned from Canvas.
+//!
+//! Example: create an exam room with its FUNKA section
+//!
+//! ```no_run
+//! use canvas_api::courses::{self, CanvasId, CourseParams, SectionParams};
+//! use canvas_api::CanvasApi;
+//!
+//! let api = CanvasApi::new("https://kth.test.instructure.com/api/v1", "XXXX");
+//! let course = CourseParams {
+//!     name: Some("Exam SF1624 2021-03-15".to_string()),
+//!     course_code: Some("SF1624".to_string()),
+//!     sis_course_id: Some("AKT.1234".to_string()),
+//!     ..CourseParams::default()
+//! };
+//! courses::create_course(&api, &CanvasId::sis("KTH"), &course).unwrap();
+//!
+//! let section = SectionParams {
+//!     name: Some("FUNKA".to_string()),
+//!     sis_section_id: Some("AKT.1234.FUNKA".to_string()),
+//!     ..SectionParams::default()
+//! };
+//! courses::create_section(&api, &CanvasId::sis("AKT.1234"), &section).unwrap();
+//! courses::publish_course(&api, &CanvasId::sis("AKT.1234")).unwrap();
+//! ```
+use crate::{CanvasApi, ItemIterator, Outcome};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a Canvas object by its Canvas ID or by its SIS ID
+#[derive(Clone, Debug, PartialEq)]
+pub enum CanvasId {
+    Id(u64),
+    Sis(String),
+}
+
+/// A course as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Course {
+    pub id: u64,
+    pub name: String,
+    pub course_code: String,
+    pub sis_course_id: Option<String>,
+    pub account_id: Option<u64>,
+    pub enrollment_term_id: Option<u64>,
+
+    /// `unpublished`, `available`, `completed` or `deleted`
+    pub workflow_state: String,
+    pub start_at: Option<String>,
+    pub end_at: Option<String>,
+}
+
+/// A section as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Section {
+    pub id: u64,
+    pub name: String,
+    pub course_id: u64,
+    pub sis_section_id: Option<String>,
+
+    /// If the section is cross-listed, the course where it was originally
+    pub nonxlist_course_id: Option<u64>,
+    pub start_at: Option<String>,
+    pub end_at: Option<String>,
+}
+
+/// Attributes of a course to create or update. Fields set to `None` are not
+/// sent.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CourseParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course_code: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sis_course_id: Option<String>,
+
+    /// Enrollment term. Accepts `sis_term_id:<id>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<String>,
+
+    /// `offer`, `claim`, `conclude`, `delete` or `undelete`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+}
+
+/// Attributes of a section to create or update. Fields set to `None` are not
+/// sent.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SectionParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sis_section_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restrict_enrollments_to_section_dates: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct CourseBody<'a> {
+    course: &'a CourseParams,
+}
+
+#[derive(Serialize)]
+struct SectionBody<'a> {
+    course_section: &'a SectionParams,
+}
+
+impl CanvasId {
+    /// Refers to an object by its SIS ID
+    pub fn sis(sis_id: &str) -> CanvasId {
+        CanvasId::Sis(sis_id.to_string())
+    }
+
+    /// Returns the ID as written in URLs for courses
+    pub fn course(&self) -> String {
+        self.segment("sis_course_id")
+    }
+
+    /// Returns the ID as written in URLs for sections
+    pub fn section(&self) -> String {
+        self.segment("sis_section_id")
+    }
+
+    /// Returns the ID as written in URLs for accounts
+    pub fn account(&self) -> String {
+        self.segment("sis_account_id")
+    }
+
+    fn segment(&self, sis_prefix: &str) -> String {
+        match self {
+            CanvasId::Id(id) => id.to_string(),
+            CanvasId::Sis(sis_id) => format!("{}:{}", sis_prefix, sis_id),
+        }
+    }
+}
+
+impl From<u64> for CanvasId {
+    fn from(id: u64) -> CanvasId {
+        CanvasId::Id(id)
+    }
+}
+
+/// Gets a course
+pub fn get_course(api: &CanvasApi, course: &CanvasId) -> Result<Course, crate::Error> {
+    Ok(api
+        .get(&format!("/courses/{}", course.course()))?
+        .error_for_status()?
+        .json()?)
+}
+
+/// Returns an iterator over the courses in an account, including the ones in
+/// its sub-accounts. To list the courses of a whole tree, call it only with
+/// the root of the tree: calling it with every account of an
+/// [`AccountTree`](crate::accounts::AccountTree) lists courses more than
+/// once.
+pub fn list_courses<'a>(api: &'a CanvasApi, account: &CanvasId) -> ItemIterator<'a, Course> {
+    api.get_paginated(&format!(
+        "/accounts/{}/courses?per_page=100",
+        account.account()
+    ))
+    .items()
+}
+
+/// Returns an iterator over the courses in an account (including its
+/// sub-accounts) that belong to an enrollment term. See the [`terms`](crate::terms) module to find the
+/// term.
+pub fn list_courses_in_term<'a>(
+    api: &'a CanvasApi,
+    account: &CanvasId,
+    enrollment_term_id: u64,
+) -> ItemIterator<'a, Course> {
+    api.get_paginated(&format!(
+        "/accounts/{}/courses?per_page=100&enrollment_term_id={}",
+        account.account(),
+        enrollment_term_id
+    ))
+    .items()
+}
+
+/// Creates a course in an account
+pub fn create_course(
+    api: &CanvasApi,
+    account: &CanvasId,
+    params: &CourseParams,
+) -> Result<Outcome<Course>, crate::Error> {
+    Outcome::from_response(api.post(
+        &format!("/accounts/{}/courses", account.account()),
+        &CourseBody { course: params },
+    )?)
+}
+
+/// Updates the attributes of a course
+pub fn update_course(
+    api: &CanvasApi,
+    course: &CanvasId,
+    params: &CourseParams,
+) -> Result<Outcome<Course>, crate::Error> {
+    Outcome::from_response(api.put(
+        &format!("/courses/{}", course.course()),
+        &CourseBody { course: params },
+    )?)
+}
+
+/// Publishes a course
+pub fn publish_course(api: &CanvasApi, course: &CanvasId) -> Result<Outcome<Course>, crate::Error> {
+    update_course(api, course, &event("offer"))
+}
+
+/// Concludes a course. Its enrollments become read-only
+pub fn conclude_course(
+    api: &CanvasApi,
+    course: &CanvasId,
+) -> Result<Outcome<Course>, crate::Error> {
+    update_course(api, course, &event("conclude"))
+}
+
+/// Gets a section
+pub fn get_section(api: &CanvasApi, section: &CanvasId) -> Result<Section, crate::Error> {
+    Ok(api
+        .get(&format!("/sections/{}", section.section()))?
+        .error_for_status()?
+        .json()?)
+}
+
+/// Creates a section in a course
+pub fn create_section(
+    api: &CanvasApi,
+    course: &CanvasId,
+    params: &SectionParams,
+) -> Result<Outcome<Section>, crate::Error> {
+    Outcome::from_response(api.post(
+        &format!("/courses/{}/sections", course.course()),
+        &SectionBody {
+            course_section: params,
+        },
+    )?)
+}
+
+/// Updates the attributes of a section
+pub fn update_section(
+    api: &CanvasApi,
+    section: &CanvasId,
+    params: &SectionParams,
+) -> Result<Outcome<Section>, crate::Error> {
+    Outcome::from_response(api.put(
+        &format!("/sections/{}", section.section()),
+        &SectionBody {
+            course_section: params,
+        },
+    )?)
+}
+
+/// Moves a section to another course (cross-listing)
+pub fn crosslist_section(
+    api: &CanvasApi,
+    section: &CanvasId,
+    new_course: &CanvasId,
+) -> Result<Outcome<Section>, crate::Error> {
+    Outcome::from_response(api.request::<()>(
+        Method::POST,
+        &format!(
+            "/sections/{}/crosslist/{}",
+            section.section(),
+            new_course.course()
+        ),
+        None,
+    )?)
+}
+
+/// Moves a cross-listed section back to its original course
+pub fn decrosslist_section(
+    api: &CanvasApi,
+    section: &CanvasId,
+) -> Result<Outcome<Section>, crate::Error> {
+    Outcome::from_response(api.delete(&format!("/sections/{}/crosslist", section.section()))?)
+}
+
+fn event(name: &str) -> CourseParams {
+    CourseParams {
+        event: Some(name.to_string()),
+        ..CourseParams::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_ids_in_urls() {
+        assert_eq!(CanvasId::from(12).course(), "12");
+        assert_eq!(CanvasId::sis("AKT.1").course(), "sis_course_id:AKT.1");
+        assert_eq!(
+            CanvasId::sis("AKT.1.FUNKA").section(),
+            "sis_section_id:AKT.1.FUNKA"
+        );
+        assert_eq!(CanvasId::sis("KTH").account(), "sis_account_id:KTH");
+    }
+
+    #[test]
+    fn does_nothing_in_dry_run() {
+        let api = CanvasApi::new("http://127.0.0.1:9", "XXXX").dry_run(true);
+        let outcome = publish_course(&api, &CanvasId::sis("AKT.1")).unwrap();
+
+        assert_eq!(outcome, Outcome::DryRun);
+        assert_eq!(
+            crosslist_section(&api, &CanvasId::sis("A"), &CanvasId::from(1)).unwrap(),
+            Outcome::DryRun
+        );
+    }
+}
@@ -1,16 +1,124 @@
 //! Helper functions to interact with the Canvas API
 //!
-//! This package contains functions to perform GET requests to the Canvas LMS
-//! API and helper functions to deal with things like pagination.
-use reqwest::blocking::{Client, Response};
+//! This package contains functions to perform requests to the Canvas LMS API
+//! and helper functions to deal with things like pagination.
+//!
+//! Requests that modify data in Canvas (everything except GET) can be run in
+//! "dry-run" mode, where they are logged (with the [`log`] crate) and
+//! described instead of sent. See [`CanvasApi::dry_run`].
+//!
+//! GET responses can be cached in disk between runs. See
+//! [`CanvasApi::cache`].
+//!
+//! Canvas URLs and tokens for different environments (production, test,
+//! beta...) can be stored as profiles in a TOML file. See the [`config`]
+//! module.
+//!
+//! Access tokens are never printed. They can be read from a command, a private
+//! file or the OS keyring. See the [`token`] module. Apps can also use OAuth2
+//! instead of personal access tokens. See the [`oauth`] module.
+//!
+//! The [`sync`] module computes and applies the changes needed to make a
+//! section contain exactly a set of enrollments.
+//!
+//! The [`courses`] module has typed operations to create, update, publish and
+//! conclude courses and sections. The [`accounts`] module walks the tree of
+//! sub-accounts. The [`terms`] module maps KTH semesters to enrollment terms.
+//!
+//! The [`submissions`] module lists assignments and submissions to export
+//! grades.
+pub mod accounts;
+mod cache;
+pub mod config;
+pub mod courses;
+pub mod oauth;
+pub mod submissions;
+pub mod sync;
+pub mod terms;
+pub mod token;
+
+pub use cache::ResponseCache;
+pub use token::{Token, TokenSource};
+
+use cache::CachedResponse;
+use oauth::TokenProvider;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, ResponseBuilderExt, StatusCode, Url};
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Header added to the synthetic responses returned in dry-run mode
+const DRY_RUN_HEADER: &str = "x-canvas-dry-run";
 
 /// Instance of a Canvas client. Contains the Canvas URL and the access token.
+///
+/// The `Debug` output does not contain the token.
 #[derive(Clone)]
 pub struct CanvasApi<'a> {
     canvas_url: &'a str,
-    canvas_token: &'a str,
+    auth: Auth,
     client: Client,
+    dry_run: bool,
+    cache: Option<ResponseCache>,
+    rate_limit: RateLimit,
+    last_request: Arc<Mutex<Option<Instant>>>,
+}
+
+/// Error returned by the requests to Canvas
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be performed, or its response could not be read
+    Http(reqwest::Error),
+
+    /// The [`TokenProvider`] could not give an access token, so the request
+    /// was not sent
+    Token(oauth::OAuthError),
+}
+
+/// Result of an operation that modifies data in Canvas
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome<T> {
+    /// The operation was performed and Canvas returned this object
+    Applied(T),
+
+    /// The operation was not performed because of the dry-run mode
+    DryRun,
+}
+
+/// How a [`CanvasApi`] authenticates its requests
+#[derive(Clone)]
+enum Auth {
+    Token(Token),
+    Provider(Arc<Mutex<dyn TokenProvider>>),
+}
+
+/// Limits to the rate of requests performed by a [`CanvasApi`].
+///
+/// Canvas throttles clients that perform too many requests by responding
+/// with "403 Forbidden (Rate Limit Exceeded)". Such requests are retried up to
+/// `max_retries` times, waiting longer every time.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RateLimit {
+    /// Maximum number of requests per second. `None` means no limit
+    pub requests_per_second: Option<f64>,
+
+    /// Number of times a throttled request is retried
+    pub max_retries: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> RateLimit {
+        RateLimit {
+            requests_per_second: None,
+            max_retries: 3,
+        }
+    }
 }
 
 /// Iterator for pages. You use it to traverse through pages in a paginated GET
@@ -27,6 +135,15 @@ pub struct ItemIterator<'a, T> {
     i: std::vec::IntoIter<T>,
 }
 
+/// Like [`ItemIterator`], but errors are returned instead of panicking:
+/// requests that fail, responses with an error status (like "404 Not Found")
+/// and bodies that are not the expected JSON. The iteration ends after the
+/// first error.
+pub struct TryItemIterator<'a, T> {
+    page_iterator: PageIterator<'a>,
+    i: std::vec::IntoIter<T>,
+}
+
 fn get_next_from_link(link: &str) -> Option<String> {
     let next_link = link.split(',').find(|&x| x.ends_with("rel=\"next\""));
 
@@ -40,7 +157,7 @@ fn get_next_from_link(link: &str) -> Option<String> {
     Some(next_url.to_string())
 }
 
-fn get_next_url(response: &Result<Response, reqwest::Error>) -> Option<String> {
+fn get_next_url(response: &Result<Response, Error>) -> Option<String> {
     let response = match response {
         Err(_) => return None,
         Ok(r) => r,
@@ -58,19 +175,11 @@ fn get_next_url(response: &Result<Response, reqwest::Error>) -> Option<String> {
 }
 
 impl<'a> Iterator for PageIterator<'a> {
-    type Item = Result<Response, reqwest::Error>;
+    type Item = Result<Response, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let client = &self.canvas_api.client;
-
-        if self.next_url == None {
-            return None;
-        }
-
-        let response = client
-            .get(self.next_url.as_ref().unwrap())
-            .bearer_auth(&self.canvas_api.canvas_token)
-            .send();
+        let next_url = self.next_url.as_ref()?;
+        let response = self.canvas_api.send_get(next_url);
 
         self.next_url = get_next_url(&response);
 
@@ -85,6 +194,13 @@ impl<'a> PageIterator<'a> {
             i: Vec::new().into_iter(),
         }
     }
+
+    pub fn try_items<T>(self) -> TryItemIterator<'a, T> {
+        TryItemIterator::<T> {
+            page_iterator: self,
+            i: Vec::new().into_iter(),
+        }
+    }
 }
 
 impl<'a, T: DeserializeOwned> Iterator for ItemIterator<'a, T> {
@@ -111,6 +227,32 @@ impl<'a, T: DeserializeOwned> Iterator for ItemIterator<'a, T> {
     }
 }
 
+impl<'a, T: DeserializeOwned> Iterator for TryItemIterator<'a, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.i.next() {
+            return Some(Ok(item));
+        }
+
+        let page = self
+            .page_iterator
+            .next()?
+            .and_then(|response| Ok(response.error_for_status()?.json::<Vec<T>>()?));
+
+        match page {
+            Ok(items) => {
+                self.i = items.into_iter();
+                self.i.next().map(Ok)
+            }
+            Err(e) => {
+                self.page_iterator.next_url = None;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 impl<'a> CanvasApi<'a> {
     /// Creates a new CanvasApi instance by giving the URL and an access token.
     ///
@@ -121,30 +263,220 @@ impl<'a> CanvasApi<'a> {
     ///
     /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX");
     /// ```
-    pub fn new(canvas_url: &'a str, canvas_token: &'a str) -> CanvasApi<'a> {
+    pub fn new(canvas_url: &'a str, canvas_token: &str) -> CanvasApi<'a> {
+        CanvasApi::with_token(canvas_url, Token::new(canvas_token))
+    }
+
+    /// Creates a new CanvasApi instance with a [`Token`], for example one
+    /// obtained from a [`TokenSource`].
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use canvas_api::{CanvasApi, TokenSource};
+    ///
+    /// let token = TokenSource::Command("pass show canvas".to_string())
+    ///     .resolve()
+    ///     .unwrap();
+    /// let api = CanvasApi::with_token("https://kth.test.instructure.com", token);
+    /// ```
+    pub fn with_token(canvas_url: &'a str, canvas_token: Token) -> CanvasApi<'a> {
+        CanvasApi::with_auth(canvas_url, Auth::Token(canvas_token))
+    }
+
+    /// Creates a new CanvasApi instance that obtains access tokens from a
+    /// [`TokenProvider`], like an OAuth2 session. Tokens are requested before
+    /// every request, so the provider can refresh them when they expire.
+    ///
+    /// See the [`oauth`] module for an example.
+    pub fn with_provider<P: TokenProvider + 'static>(
+        canvas_url: &'a str,
+        provider: P,
+    ) -> CanvasApi<'a> {
+        CanvasApi::with_auth(canvas_url, Auth::Provider(Arc::new(Mutex::new(provider))))
+    }
+
+    fn with_auth(canvas_url: &'a str, auth: Auth) -> CanvasApi<'a> {
         CanvasApi {
-            canvas_token,
+            auth,
             canvas_url,
             client: Client::new(),
+            dry_run: false,
+            cache: None,
+            rate_limit: RateLimit::default(),
+            last_request: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Sets the limits to the rate of requests. Clones of this instance (like
+    /// the ones used by [`PageIterator`]) share the same limit.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use canvas_api::{CanvasApi, RateLimit};
+    ///
+    /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX").rate_limit(RateLimit {
+    ///     requests_per_second: Some(5.0),
+    ///     max_retries: 3,
+    /// });
+    /// ```
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> CanvasApi<'a> {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Caches the responses of GET requests (also the pages of paginated
+    /// requests) in disk. See [`ResponseCache`] to learn when a cached
+    /// response is used.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use canvas_api::{CanvasApi, ResponseCache};
+    /// use std::time::Duration;
+    ///
+    /// let cache = ResponseCache::new(".canvas-cache", Duration::from_secs(24 * 3600));
+    /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX").cache(cache);
+    /// ```
+    pub fn cache(mut self, cache: ResponseCache) -> CanvasApi<'a> {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Returns a copy of this instance that does not use the cache. Use it to
+    /// bypass the cache in a single call:
+    ///
+    /// ```no_run
+    /// # use canvas_api::{CanvasApi, ResponseCache};
+    /// # use std::time::Duration;
+    /// # let cache = ResponseCache::new(".canvas-cache", Duration::from_secs(3600));
+    /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX").cache(cache);
+    /// let fresh = api.without_cache().get("/accounts/1").unwrap();
+    /// ```
+    pub fn without_cache(&self) -> CanvasApi<'a> {
+        CanvasApi {
+            cache: None,
+            ..self.clone()
         }
     }
 
+    /// Enables or disables the dry-run mode. In dry-run mode, requests that
+    /// modify data (POST, PUT, DELETE...) are not sent to Canvas. Instead, a
+    /// synthetic response is returned. GET requests are sent normally.
+    ///
+    /// Every skipped request is logged at the `info` level with its method,
+    /// URL and JSON payload. The token is not logged. Programs see the log if
+    /// they install a logger (like `env_logger`), and can also print the body
+    /// of the synthetic responses.
+    ///
+    /// The synthetic response has status 200 and a JSON body that describes
+    /// the request that would have been sent:
+    ///
+    /// ```json
+    /// {"dry_run": true, "method": "POST", "url": "...", "payload": {...}}
+    /// ```
+    ///
+    /// Use [`is_dry_run_response`] to tell synthetic responses apart.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use canvas_api::CanvasApi;
+    ///
+    /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX").dry_run(true);
+    /// let response = api.delete("/courses/1").unwrap();
+    ///
+    /// assert!(canvas_api::is_dry_run_response(&response));
+    /// ```
+    pub fn dry_run(mut self, dry_run: bool) -> CanvasApi<'a> {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns `true` if this instance is in dry-run mode
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Performs a GET request to and endpoint in Canvas. This function does
     /// not handle any error
     ///
     /// Example:
     ///
-    /// ```
+    /// ```no_run
     /// use canvas_api::CanvasApi;
     ///
     /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX");
     /// let result = api.get("/accounts/1").unwrap();
     /// ```
-    pub fn get(&self, endpoint: &str) -> Result<Response, reqwest::Error> {
-        self.client
-            .get(&format!("{}{}", self.canvas_url, endpoint))
-            .bearer_auth(&self.canvas_token)
-            .send()
+    pub fn get(&self, endpoint: &str) -> Result<Response, Error> {
+        self.request::<()>(Method::GET, endpoint, None)
+    }
+
+    /// Performs a POST request to an endpoint in Canvas with `body` as JSON
+    /// payload. In dry-run mode, the request is not sent.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use canvas_api::CanvasApi;
+    /// use std::collections::HashMap;
+    ///
+    /// let api = CanvasApi::new("https://kth.test.instructure.com", "XXXX");
+    /// let mut body = HashMap::new();
+    /// body.insert("name", "New section");
+    ///
+    /// let result = api.post("/courses/1/sections", &body).unwrap();
+    /// ```
+    pub fn post<T: Serialize + ?Sized>(&self, endpoint: &str, body: &T) -> Result<Response, Error> {
+        self.request(Method::POST, endpoint, Some(body))
+    }
+
+    /// Performs a PUT request to an endpoint in Canvas with `body` as JSON
+    /// payload. In dry-run mode, the request is not sent.
+    pub fn put<T: Serialize + ?Sized>(&self, endpoint: &str, body: &T) -> Result<Response, Error> {
+        self.request(Method::PUT, endpoint, Some(body))
+    }
+
+    /// Performs a DELETE request to an endpoint in Canvas. In dry-run mode, the
+    /// request is not sent.
+    pub fn delete(&self, endpoint: &str) -> Result<Response, Error> {
+        self.request::<()>(Method::DELETE, endpoint, None)
+    }
+
+    /// Performs a request with any method to an endpoint in Canvas, with an
+    /// optional JSON payload. This is the function used by [`get`], [`post`],
+    /// [`put`] and [`delete`], and it is the one to use for other methods.
+    ///
+    /// In dry-run mode, any request that is not a GET is not sent.
+    ///
+    /// [`get`]: #method.get
+    /// [`post`]: #method.post
+    /// [`put`]: #method.put
+    /// [`delete`]: #method.delete
+    pub fn request<T: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&T>,
+    ) -> Result<Response, Error> {
+        let url = format!("{}{}", self.canvas_url, endpoint);
+
+        if method == Method::GET && body.is_none() {
+            return self.send_get(&url);
+        }
+
+        if self.dry_run && method != Method::GET {
+            return Ok(dry_run_response(method, &url, body));
+        }
+
+        let request = self.authorize(self.client.request(method, &url))?;
+
+        match body {
+            None => self.send(request),
+            Some(body) => self.send(request.json(body)),
+        }
     }
 
     /// Returns an iterator that can be used to perform requests to a paginated
@@ -154,12 +486,241 @@ impl<'a> CanvasApi<'a> {
     /// through pages
     ///
     /// [`PageIterator`]: struct.PageIterator.html
-    pub fn get_paginated(&self, endpoint: &str) -> PageIterator {
+    pub fn get_paginated(&self, endpoint: &str) -> PageIterator<'_> {
         PageIterator {
             canvas_api: self.clone(),
             next_url: Some(format!("{}{}", self.canvas_url, endpoint)),
         }
     }
+
+    /// Performs a GET request to a full URL, using the cache if there is one.
+    /// Fresh cached responses are returned without asking for a token
+    fn send_get(&self, url: &str) -> Result<Response, Error> {
+        let cache = match &self.cache {
+            None => return self.send(self.authorize(self.client.get(url))?),
+            Some(cache) => cache,
+        };
+
+        let cached = cache.load(url);
+
+        if let Some(cached) = &cached {
+            if cached.is_fresh(cache.ttl()) {
+                return Ok(cached.to_response(url));
+            }
+        }
+
+        let mut request = self.authorize(self.client.get(url))?;
+
+        if let Some(cached) = &cached {
+            if cached.can_revalidate() {
+                request = request.headers(cached.conditional_headers());
+            }
+        }
+
+        let response = self.send(request)?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(mut cached) = cached {
+                cached.touch();
+                cache.store(&cached);
+                return Ok(cached.to_response(url));
+            }
+        }
+
+        if !response.status().is_success() {
+            return Ok(response);
+        }
+
+        let fresh = CachedResponse::from_response(url, response)?;
+        cache.store(&fresh);
+
+        Ok(fresh.to_response(url))
+    }
+
+    /// Adds the access token to a request. Fails if the token provider
+    /// cannot give a token
+    fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder, Error> {
+        let token = match &self.auth {
+            Auth::Token(token) => token.clone(),
+            Auth::Provider(provider) => provider.lock().unwrap().access_token()?,
+        };
+
+        Ok(request.bearer_auth(token.expose()))
+    }
+
+    /// Sends a request respecting the rate limit, retrying it if Canvas
+    /// throttles it.
+    fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let mut attempt = 0;
+
+        loop {
+            self.wait_for_turn();
+
+            // Requests with streamed bodies cannot be cloned nor retried
+            let retry = match request.try_clone() {
+                None => return Ok(request.send()?),
+                Some(retry) => retry,
+            };
+
+            let response = retry.send()?;
+
+            if !is_throttled(&response) || attempt >= self.rate_limit.max_retries {
+                return Ok(response);
+            }
+
+            attempt += 1;
+            thread::sleep(Duration::from_secs(1 << attempt));
+        }
+    }
+
+    /// Waits until performing another request does not exceed the rate limit
+    fn wait_for_turn(&self) {
+        let requests_per_second = match self.rate_limit.requests_per_second {
+            Some(r) if r > 0.0 => r,
+            _ => return,
+        };
+        let interval = Duration::from_secs_f64(1.0 / requests_per_second);
+        let mut last_request = self.last_request.lock().unwrap();
+
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+
+            if elapsed < interval {
+                thread::sleep(interval - elapsed);
+            }
+        }
+
+        *last_request = Some(Instant::now());
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Auth::Token(token) => write!(f, "{:?}", token),
+            Auth::Provider(_) => write!(f, "TokenProvider"),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for CanvasApi<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CanvasApi")
+            .field("canvas_url", &self.canvas_url)
+            .field("auth", &self.auth)
+            .field("dry_run", &self.dry_run)
+            .field("cache", &self.cache)
+            .field("rate_limit", &self.rate_limit)
+            .finish()
+    }
+}
+
+impl<T: DeserializeOwned> Outcome<T> {
+    /// Reads the object returned by Canvas. Fails if the response has an
+    /// error status
+    pub fn from_response(response: Response) -> Result<Outcome<T>, Error> {
+        if is_dry_run_response(&response) {
+            return Ok(Outcome::DryRun);
+        }
+
+        Ok(Outcome::Applied(response.error_for_status()?.json()?))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "{}", e),
+            Error::Token(e) => write!(f, "Error when getting an access token: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Token(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Http(e)
+    }
+}
+
+impl From<oauth::OAuthError> for Error {
+    fn from(e: oauth::OAuthError) -> Error {
+        Error::Token(e)
+    }
+}
+
+impl<T> Outcome<T> {
+    /// Returns the object, or `None` in dry-run mode
+    pub fn applied(self) -> Option<T> {
+        match self {
+            Outcome::Applied(value) => Some(value),
+            Outcome::DryRun => None,
+        }
+    }
+}
+
+/// Returns `true` if Canvas rejected the request because of its rate limit
+fn is_throttled(response: &Response) -> bool {
+    if response.status() != StatusCode::FORBIDDEN {
+        return false;
+    }
+
+    response
+        .headers()
+        .get("x-rate-limit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|remaining| remaining <= 0.0)
+        .unwrap_or(false)
+}
+
+/// Returns `true` if the response is a synthetic one, returned by a
+/// [`CanvasApi`] in dry-run mode instead of performing the request.
+pub fn is_dry_run_response(response: &Response) -> bool {
+    response.headers().contains_key(DRY_RUN_HEADER)
+}
+
+/// Builds the synthetic response returned in dry-run mode, which describes
+/// the request that would have been sent, and logs the request
+fn dry_run_response<T: Serialize + ?Sized>(
+    method: Method,
+    url: &str,
+    body: Option<&T>,
+) -> Response {
+    let payload = body
+        .map(|b| serde_json::to_value(b).unwrap_or(serde_json::Value::Null))
+        .unwrap_or(serde_json::Value::Null);
+
+    log::info!("Dry run: {} {} {}", method, url, payload);
+
+    let description = serde_json::json!({
+        "dry_run": true,
+        "method": method.as_str(),
+        "url": url,
+        "payload": payload,
+    });
+
+    let mut builder = http::Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .header(DRY_RUN_HEADER, "true");
+
+    if let Ok(url) = Url::parse(url) {
+        builder = builder.url(url);
+    }
+
+    builder
+        .body(description.to_string())
+        .expect("Error when building the dry-run response")
+        .into()
 }
 
 #[cfg(test)]
@@ -177,4 +738,111 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn dry_run_only_skips_mutating_requests() {
+        // The URL is not reachable: any request that is actually sent fails
+        let api = CanvasApi::new("http://127.0.0.1:9", "XXXX").dry_run(true);
+
+        let response = api.post("/courses/1/sections", &[("name", "A")]).unwrap();
+        assert!(is_dry_run_response(&response));
+
+        let body = response.json::<serde_json::Value>().unwrap();
+        assert_eq!(body["method"], "POST");
+        assert_eq!(body["url"], "http://127.0.0.1:9/courses/1/sections");
+        assert_eq!(body["payload"][0][1], "A");
+
+        assert!(is_dry_run_response(&api.delete("/courses/1").unwrap()));
+        assert!(api.get("/courses/1").is_err());
+    }
+
+    #[test]
+    fn logs_skipped_requests() {
+        struct Capture(Mutex<Vec<String>>);
+
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+
+        let api = CanvasApi::new("http://127.0.0.1:9", "secret").dry_run(true);
+        api.put("/sections/7", &[("name", "B")]).unwrap();
+
+        let lines = LOGGER.0.lock().unwrap();
+        let line = lines
+            .iter()
+            .find(|l| l.contains("/sections/7"))
+            .expect("The request is not logged");
+        assert_eq!(
+            line,
+            "Dry run: PUT http://127.0.0.1:9/sections/7 [[\"name\",\"B\"]]"
+        );
+        assert!(!lines.iter().any(|l| l.contains("secret")));
+    }
+
+    #[test]
+    fn returns_token_errors() {
+        struct Failing;
+
+        impl TokenProvider for Failing {
+            fn access_token(&mut self) -> Result<Token, oauth::OAuthError> {
+                Err(oauth::OAuthError::NoRefreshToken)
+            }
+        }
+
+        let api = CanvasApi::with_provider("http://127.0.0.1:9", Failing);
+
+        assert!(matches!(
+            api.get("/courses/1"),
+            Err(Error::Token(oauth::OAuthError::NoRefreshToken))
+        ));
+    }
+
+    #[test]
+    fn reads_fresh_responses_without_a_token() {
+        struct Failing;
+
+        impl TokenProvider for Failing {
+            fn access_token(&mut self) -> Result<Token, oauth::OAuthError> {
+                Err(oauth::OAuthError::NoRefreshToken)
+            }
+        }
+
+        let directory =
+            std::env::temp_dir().join(format!("canvas-cache-token-{}", std::process::id()));
+        let cache = ResponseCache::new(&directory, Duration::from_secs(60));
+        let url = "http://127.0.0.1:9/courses/1";
+        let response: Response = http::Response::builder()
+            .status(200)
+            .body("{\"id\":1}")
+            .unwrap()
+            .into();
+        cache.store(&cache::CachedResponse::from_response(url, response).unwrap());
+
+        let api = CanvasApi::with_provider("http://127.0.0.1:9", Failing).cache(cache.clone());
+        let body = api.get("/courses/1").map(|r| r.text().unwrap());
+        let uncached = api.get("/courses/2");
+        cache.clear().unwrap();
+
+        assert_eq!(body.unwrap(), "{\"id\":1}");
+        assert!(matches!(uncached, Err(Error::Token(_))));
+    }
+
+    #[test]
+    fn debug_hides_the_token() {
+        let api = CanvasApi::new("https://kth.test.instructure.com", "secret");
+
+        assert!(!format!("{:?}", api).contains("secret"));
+    }
 }
@@ -0,0 +1,732 @@
+//! OAuth2 flows to obtain access tokens without personal access tokens
+//!
+//! Two flows are supported:
+//!
+//! - The **authorization code** flow with a Canvas developer key. A user
+//!   authorizes the app once in the browser, and the obtained tokens are
+//!   stored in a file and refreshed automatically. See [`OAuthSession`].
+//! - The **client credentials** flow of LTI Advantage, for service-to-service
+//!   access. The app signs a JWT with its private key. See [`LtiSession`].
+//!
+//! Both implement [`TokenProvider`], so they can be given to
+//! [`CanvasApi::with_provider`](crate::CanvasApi::with_provider).
+use crate::Token;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Tokens are refreshed when they expire in less than this number of seconds
+const REFRESH_MARGIN: u64 = 60;
+
+/// Time that [`OAuthClient::authorize_interactively`] waits for the browser
+/// to be redirected back
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Something that gives access tokens and renews them when needed
+pub trait TokenProvider: Send {
+    /// Returns a valid access token
+    fn access_token(&mut self) -> Result<Token, OAuthError>;
+}
+
+/// A Canvas developer key used in the authorization code flow
+#[derive(Clone, Debug)]
+pub struct OAuthClient {
+    /// URL of the Canvas instance, without `/api/v1`
+    pub canvas_url: String,
+    pub client_id: String,
+    pub client_secret: Token,
+    pub redirect_uri: String,
+}
+
+/// Tokens obtained in the authorization code flow
+#[derive(Clone, Debug)]
+pub struct OAuthTokens {
+    pub access_token: Token,
+    pub refresh_token: Option<Token>,
+
+    /// Expiration time, in seconds since the UNIX epoch
+    pub expires_at: Option<u64>,
+}
+
+/// Tokens of the authorization code flow that are refreshed automatically and
+/// optionally persisted in a file.
+///
+/// Example:
+///
+/// ```no_run
+/// use canvas_api::oauth::{OAuthClient, OAuthSession};
+/// use canvas_api::{CanvasApi, Token};
+///
+/// let client = OAuthClient {
+///     canvas_url: "https://kth.test.instructure.com".to_string(),
+///     client_id: "10000000000001".to_string(),
+///     client_secret: Token::new("XXXX"),
+///     redirect_uri: "http://localhost:8765/callback".to_string(),
+/// };
+///
+/// // Opens the stored session or, if there is none, asks the user to
+/// // authorize the app in the browser
+/// let session = OAuthSession::open(client, ".canvas-oauth.json").unwrap();
+/// let api = CanvasApi::with_provider("https://kth.test.instructure.com/api/v1", session);
+/// ```
+pub struct OAuthSession {
+    client: OAuthClient,
+    tokens: OAuthTokens,
+    store: Option<PathBuf>,
+    http: Client,
+}
+
+/// Settings of an LTI Advantage tool for the client credentials flow
+#[derive(Clone, Debug)]
+pub struct LtiClientCredentials {
+    /// Token endpoint. In Canvas, `https://<canvas>/login/oauth2/token`
+    pub token_url: String,
+    pub client_id: String,
+
+    /// Private key (PEM) whose public key is registered in the developer key
+    pub private_key_pem: Token,
+
+    /// ID of the key in the JWK set of the tool, if any
+    pub key_id: Option<String>,
+
+    /// LTI Advantage scopes, like
+    /// `https://purl.imsglobal.org/spec/lti-nrps/scope/contextmembership.readonly`
+    pub scopes: Vec<String>,
+}
+
+/// Access tokens obtained with the LTI Advantage client credentials flow.
+/// There are no refresh tokens in this flow: a new token is requested when
+/// the current one is about to expire.
+pub struct LtiSession {
+    credentials: LtiClientCredentials,
+    token: Option<(Token, u64)>,
+    http: Client,
+}
+
+/// Errors that can happen in the OAuth2 flows
+#[derive(Debug)]
+pub enum OAuthError {
+    /// The request could not be performed
+    Http(reqwest::Error),
+
+    /// The token endpoint responded with an error
+    Rejected(u16, String),
+
+    /// The user denied the authorization, or the callback was malformed
+    Authorization(String),
+
+    /// The token file could not be read or written
+    Store(PathBuf, io::Error),
+
+    /// The local listener for the redirect failed
+    Listener(io::Error),
+
+    /// The JWT could not be signed
+    Jwt(jsonwebtoken::errors::Error),
+
+    /// The access token expired and there is no refresh token
+    NoRefreshToken,
+
+    /// The Canvas URL of the [`OAuthClient`] is not valid
+    InvalidUrl(String),
+}
+
+/// Token response from Canvas
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+/// Format of the file where the tokens are persisted
+#[derive(Serialize, Deserialize)]
+struct StoredTokens {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<u64>,
+}
+
+/// Claims of the JWT used in the client credentials flow
+#[derive(Serialize)]
+struct ClientAssertion<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+    jti: String,
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OAuthError::Http(e) => write!(f, "Request to the token endpoint failed: {}", e),
+            OAuthError::Rejected(status, body) => {
+                write!(f, "Token endpoint responded {}: {}", status, body)
+            }
+            OAuthError::Authorization(e) => write!(f, "Authorization failed: {}", e),
+            OAuthError::Store(path, e) => write!(f, "Token file `{}`: {}", path.display(), e),
+            OAuthError::Listener(e) => write!(f, "Local redirect listener failed: {}", e),
+            OAuthError::Jwt(e) => write!(f, "Cannot sign the client assertion: {}", e),
+            OAuthError::NoRefreshToken => write!(f, "The token expired and cannot be refreshed"),
+            OAuthError::InvalidUrl(url) => write!(f, "Invalid Canvas URL `{}`", url),
+        }
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+impl From<reqwest::Error> for OAuthError {
+    fn from(e: reqwest::Error) -> OAuthError {
+        OAuthError::Http(e)
+    }
+}
+
+impl OAuthClient {
+    /// Returns the URL where the user authorizes the app
+    pub fn authorize_url(&self, state: &str) -> Result<String, OAuthError> {
+        let mut url = Url::parse(&format!("{}/login/oauth2/auth", self.canvas_url))
+            .map_err(|_| OAuthError::InvalidUrl(self.canvas_url.clone()))?;
+
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("response_type", "code")
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("state", state);
+
+        Ok(url.to_string())
+    }
+
+    /// Exchanges an authorization code for tokens, using the HTTP client
+    /// `http`
+    pub fn exchange_code(&self, http: &Client, code: &str) -> Result<OAuthTokens, OAuthError> {
+        self.request_tokens(
+            http,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.redirect_uri),
+            ],
+        )
+    }
+
+    /// Obtains a new access token. Canvas does not return a new refresh
+    /// token, so the current one is kept.
+    pub fn refresh(&self, http: &Client, tokens: &OAuthTokens) -> Result<OAuthTokens, OAuthError> {
+        let refresh_token = tokens
+            .refresh_token
+            .as_ref()
+            .ok_or(OAuthError::NoRefreshToken)?;
+
+        let refreshed = self.request_tokens(
+            http,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.expose()),
+            ],
+        )?;
+
+        Ok(OAuthTokens {
+            refresh_token: refreshed
+                .refresh_token
+                .or_else(|| tokens.refresh_token.clone()),
+            ..refreshed
+        })
+    }
+
+    /// Runs the whole authorization code flow for command-line apps: prints
+    /// the URL that the user must open, waits for Canvas to redirect the
+    /// browser to `redirect_uri` (which must point to `localhost`) and
+    /// exchanges the code. Fails if the browser is not redirected in 5
+    /// minutes.
+    pub fn authorize_interactively(&self, http: &Client) -> Result<OAuthTokens, OAuthError> {
+        let redirect = Url::parse(&self.redirect_uri)
+            .map_err(|e| OAuthError::Authorization(format!("Invalid redirect URI: {}", e)))?;
+        let port = redirect.port_or_known_default().unwrap_or(80);
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(OAuthError::Listener)?;
+        let state = random_string();
+        let url = self.authorize_url(&state)?;
+
+        println!("Open this URL in your browser to authorize the app:");
+        println!("{}", url);
+
+        let code = wait_for_code(&listener, redirect.path(), &state, AUTHORIZATION_TIMEOUT)?;
+
+        self.exchange_code(http, &code)
+    }
+
+    fn request_tokens(
+        &self,
+        http: &Client,
+        params: &[(&str, &str)],
+    ) -> Result<OAuthTokens, OAuthError> {
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.expose()),
+        ];
+        form.extend_from_slice(params);
+
+        let response = http
+            .post(format!("{}/login/oauth2/token", self.canvas_url))
+            .form(&form)
+            .send()?;
+
+        parse_token_response(response)
+    }
+}
+
+impl OAuthTokens {
+    /// Returns `true` if the access token has expired or is about to expire
+    pub fn expires_soon(&self) -> bool {
+        match self.expires_at {
+            None => false,
+            Some(expires_at) => now() + REFRESH_MARGIN >= expires_at,
+        }
+    }
+
+    /// Reads tokens stored with [`OAuthTokens::save`]
+    pub fn load(path: &Path) -> Result<OAuthTokens, OAuthError> {
+        let content =
+            fs::read_to_string(path).map_err(|e| OAuthError::Store(path.to_path_buf(), e))?;
+        let stored = serde_json::from_str::<StoredTokens>(&content)
+            .map_err(|e| OAuthError::Store(path.to_path_buf(), io::Error::other(e)))?;
+
+        Ok(OAuthTokens {
+            access_token: Token::new(stored.access_token),
+            refresh_token: stored.refresh_token.map(Token::new),
+            expires_at: stored.expires_at,
+        })
+    }
+
+    /// Writes the tokens in a file that only its owner can read
+    pub fn save(&self, path: &Path) -> Result<(), OAuthError> {
+        let stored = StoredTokens {
+            access_token: self.access_token.expose().to_string(),
+            refresh_token: self.refresh_token.as_ref().map(|t| t.expose().to_string()),
+            expires_at: self.expires_at,
+        };
+        let content = serde_json::to_string_pretty(&stored).expect("Error when encoding tokens");
+
+        write_private_file(path, &content).map_err(|e| OAuthError::Store(path.to_path_buf(), e))
+    }
+}
+
+impl OAuthSession {
+    /// Creates a session with tokens that are already obtained. They are not
+    /// persisted.
+    pub fn new(client: OAuthClient, tokens: OAuthTokens) -> OAuthSession {
+        OAuthSession {
+            client,
+            tokens,
+            store: None,
+            http: Client::new(),
+        }
+    }
+
+    /// Opens a session persisted in `path`. If the file does not exist, runs
+    /// the interactive authorization (see
+    /// [`OAuthClient::authorize_interactively`]) and stores the tokens.
+    pub fn open<P: Into<PathBuf>>(
+        client: OAuthClient,
+        path: P,
+    ) -> Result<OAuthSession, OAuthError> {
+        let path = path.into();
+        let http = Client::new();
+        let tokens = match path.exists() {
+            true => OAuthTokens::load(&path)?,
+            false => {
+                let tokens = client.authorize_interactively(&http)?;
+                tokens.save(&path)?;
+                tokens
+            }
+        };
+
+        Ok(OAuthSession {
+            client,
+            tokens,
+            store: Some(path),
+            http,
+        })
+    }
+
+    /// Returns the current tokens
+    pub fn tokens(&self) -> &OAuthTokens {
+        &self.tokens
+    }
+
+    /// Deletes the access token in Canvas (`DELETE /login/oauth2/token`) and
+    /// the persisted file
+    pub fn logout(self) -> Result<(), OAuthError> {
+        self.http
+            .delete(format!("{}/login/oauth2/token", self.client.canvas_url))
+            .bearer_auth(self.tokens.access_token.expose())
+            .send()?;
+
+        if let Some(path) = &self.store {
+            fs::remove_file(path).map_err(|e| OAuthError::Store(path.clone(), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TokenProvider for OAuthSession {
+    fn access_token(&mut self) -> Result<Token, OAuthError> {
+        if self.tokens.expires_soon() {
+            self.tokens = self.client.refresh(&self.http, &self.tokens)?;
+
+            if let Some(path) = &self.store {
+                self.tokens.save(path)?;
+            }
+        }
+
+        Ok(self.tokens.access_token.clone())
+    }
+}
+
+impl LtiSession {
+    pub fn new(credentials: LtiClientCredentials) -> LtiSession {
+        LtiSession {
+            credentials,
+            token: None,
+            http: Client::new(),
+        }
+    }
+
+    /// Builds the signed JWT that authenticates the tool
+    fn client_assertion(&self) -> Result<String, OAuthError> {
+        let credentials = &self.credentials;
+        let iat = now();
+        let claims = ClientAssertion {
+            iss: &credentials.client_id,
+            sub: &credentials.client_id,
+            aud: &credentials.token_url,
+            iat,
+            exp: iat + 300,
+            jti: random_string(),
+        };
+        let header = Header {
+            kid: credentials.key_id.clone(),
+            ..Header::new(Algorithm::RS256)
+        };
+        let key = EncodingKey::from_rsa_pem(credentials.private_key_pem.expose().as_bytes())
+            .map_err(OAuthError::Jwt)?;
+
+        jsonwebtoken::encode(&header, &claims, &key).map_err(OAuthError::Jwt)
+    }
+
+    fn request_token(&self) -> Result<OAuthTokens, OAuthError> {
+        let assertion = self.client_assertion()?;
+        let scope = self.credentials.scopes.join(" ");
+        let response = self
+            .http
+            .post(&self.credentials.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                (
+                    "client_assertion_type",
+                    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                ),
+                ("client_assertion", &assertion),
+                ("scope", &scope),
+            ])
+            .send()?;
+
+        parse_token_response(response)
+    }
+}
+
+impl TokenProvider for LtiSession {
+    fn access_token(&mut self) -> Result<Token, OAuthError> {
+        if let Some((token, expires_at)) = &self.token {
+            if now() + REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let tokens = self.request_token()?;
+        let expires_at = tokens.expires_at.unwrap_or_else(|| now() + 3600);
+        self.token = Some((tokens.access_token.clone(), expires_at));
+
+        Ok(tokens.access_token)
+    }
+}
+
+fn parse_token_response(response: reqwest::blocking::Response) -> Result<OAuthTokens, OAuthError> {
+    let status = response.status();
+
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        return Err(OAuthError::Rejected(status.as_u16(), body));
+    }
+
+    let body = response.json::<TokenResponse>()?;
+
+    Ok(OAuthTokens {
+        access_token: Token::new(body.access_token),
+        refresh_token: body.refresh_token.map(Token::new),
+        expires_at: body.expires_in.map(|seconds| now() + seconds),
+    })
+}
+
+/// Accepts connections until the browser is redirected to `path` and returns
+/// the authorization code. Fails if that does not happen before `timeout`
+fn wait_for_code(
+    listener: &TcpListener,
+    path: &str,
+    state: &str,
+    timeout: Duration,
+) -> Result<String, OAuthError> {
+    let deadline = Instant::now() + timeout;
+    listener
+        .set_nonblocking(true)
+        .map_err(OAuthError::Listener)?;
+
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(OAuthError::Authorization(format!(
+                        "No redirect received in {} seconds",
+                        timeout.as_secs()
+                    )));
+                }
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => return Err(OAuthError::Listener(e)),
+        };
+        let mut request_line = String::new();
+        let read = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(Duration::from_secs(10))))
+            .and_then(|_| BufReader::new(&stream).read_line(&mut request_line));
+
+        // Browsers open connections that they do not use (preconnects) or
+        // close early. Only the deadline stops the wait
+        if let Err(e) = read {
+            log::warn!("Ignoring a connection to the redirect listener: {}", e);
+            continue;
+        }
+
+        let result = match parse_callback(&request_line, path) {
+            // Other requests, like the browser asking for a favicon
+            None => {
+                stream
+                    .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                    .ok();
+                continue;
+            }
+            Some(params) => check_callback(params, state),
+        };
+
+        let message = match &result {
+            Ok(_) => "Authorization completed. You can close this window.",
+            Err(_) => "Authorization failed. Go back to the terminal.",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )
+        .ok();
+
+        return result;
+    }
+}
+
+/// Returns the query parameters of a request line like
+/// `GET /callback?code=X&state=Y HTTP/1.1` if it is a request to `path`
+fn parse_callback(request_line: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let target = request_line.split_whitespace().nth(1)?;
+    let url = Url::parse(&format!("http://localhost{}", target)).ok()?;
+
+    if url.path() != path {
+        return None;
+    }
+
+    Some(url.query_pairs().into_owned().collect())
+}
+
+fn check_callback(params: Vec<(String, String)>, state: &str) -> Result<String, OAuthError> {
+    let get = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    if let Some(error) = get("error") {
+        return Err(OAuthError::Authorization(error));
+    }
+
+    if get("state").as_deref() != Some(state) {
+        return Err(OAuthError::Authorization(
+            "State does not match".to_string(),
+        ));
+    }
+
+    get("code").ok_or_else(|| OAuthError::Authorization("No code received".to_string()))
+}
+
+fn write_private_file(path: &Path, content: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+
+    // `mode` only applies when the file is created. Existing files are
+    // restricted before anything is written in them
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(content.as_bytes())
+}
+
+fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OAuthClient {
+        OAuthClient {
+            canvas_url: "https://canvas.test".to_string(),
+            client_id: "123".to_string(),
+            client_secret: Token::new("secret"),
+            redirect_uri: "http://localhost:8765/callback".to_string(),
+        }
+    }
+
+    #[test]
+    fn builds_authorize_url() {
+        assert_eq!(
+            client().authorize_url("abc").unwrap(),
+            "https://canvas.test/login/oauth2/auth?client_id=123&response_type=code&redirect_uri=http%3A%2F%2Flocalhost%3A8765%2Fcallback&state=abc"
+        );
+    }
+
+    #[test]
+    fn reads_the_callback() {
+        let params = parse_callback("GET /callback?code=X&state=S HTTP/1.1\r\n", "/callback");
+
+        assert!(parse_callback("GET /favicon.ico HTTP/1.1\r\n", "/callback").is_none());
+        assert_eq!(check_callback(params.clone().unwrap(), "S").unwrap(), "X");
+        assert!(check_callback(params.unwrap(), "other").is_err());
+
+        let denied = parse_callback(
+            "GET /callback?error=access_denied&state=S HTTP/1.1",
+            "/callback",
+        );
+        assert!(check_callback(denied.unwrap(), "S").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        let client = OAuthClient {
+            canvas_url: "not a url".to_string(),
+            ..client()
+        };
+
+        assert!(matches!(
+            client.authorize_url("abc"),
+            Err(OAuthError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn stops_waiting_for_the_redirect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let result = wait_for_code(&listener, "/callback", "S", Duration::from_millis(200));
+
+        assert!(matches!(result, Err(OAuthError::Authorization(_))));
+    }
+
+    #[test]
+    fn ignores_broken_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let browser = thread::spawn(move || {
+            // Not UTF-8: reading the request line fails
+            let mut broken = std::net::TcpStream::connect(address).unwrap();
+            broken.write_all(b"\xff\xfe\r\n").unwrap();
+            drop(broken);
+
+            let mut redirect = std::net::TcpStream::connect(address).unwrap();
+            redirect
+                .write_all(b"GET /callback?code=C&state=S HTTP/1.1\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            io::Read::read_to_string(&mut redirect, &mut response).ok();
+        });
+
+        let result = wait_for_code(&listener, "/callback", "S", Duration::from_secs(5));
+        browser.join().unwrap();
+
+        assert_eq!(result.unwrap(), "C");
+    }
+
+    #[test]
+    fn persists_tokens() {
+        let path = std::env::temp_dir().join(format!("canvas-oauth-{}.json", std::process::id()));
+        let tokens = OAuthTokens {
+            access_token: Token::new("access"),
+            refresh_token: Some(Token::new("refresh")),
+            expires_at: Some(now() + 30),
+        };
+
+        assert!(tokens.expires_soon());
+        fs::write(&path, "").unwrap();
+        tokens.save(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let loaded = OAuthTokens::load(&path).unwrap();
+        assert_eq!(loaded.access_token.expose(), "access");
+        assert_eq!(loaded.refresh_token.unwrap().expose(), "refresh");
+        assert_eq!(loaded.expires_at, tokens.expires_at);
+
+        fs::remove_file(path).unwrap();
+    }
+}
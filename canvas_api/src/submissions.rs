@@ -0,0 +1,230 @@
+//! Assignments, assignment groups and submissions
+//!
+//! Used to export grades. [`list_submissions`] streams the submissions of all
+//! students in a course: pages are requested while the iterator is consumed,
+//! so big courses are never loaded in memory at once.
+//!
+//! The iterators return errors as items (for example if the course does not
+//! exist), and end after the first one.
+//!
+//! Example: print every score in a course
+//!
+//! ```no_run
+//! use canvas_api::courses::CanvasId;
+//! use canvas_api::submissions;
+//! use canvas_api::CanvasApi;
+//!
+//! let api = CanvasApi::new("https://kth.test.instructure.com/api/v1", "XXXX");
+//!
+//! for submission in submissions::list_submissions(&api, &CanvasId::sis("SF1624HT201")) {
+//!     let submission = submission.unwrap();
+//!     let assignment = submission.assignment.as_ref().map(|a| a.name.as_str());
+//!     println!(
+//!         "{} {:?} {:?}",
+//!         submission.user_id, assignment, submission.score
+//!     );
+//! }
+//! ```
+use crate::courses::CanvasId;
+use crate::{CanvasApi, TryItemIterator};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+/// An assignment as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub id: u64,
+    pub name: String,
+    pub course_id: u64,
+    pub assignment_group_id: Option<u64>,
+    pub position: Option<u32>,
+    pub points_possible: Option<f64>,
+
+    /// `points`, `percent`, `letter_grade`, `gpa_scale`, `pass_fail` or
+    /// `not_graded`
+    pub grading_type: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub published: Option<bool>,
+}
+
+/// An assignment group as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AssignmentGroup {
+    pub id: u64,
+    pub name: String,
+    pub position: Option<u32>,
+
+    /// Weight of the group in the final grade, if the course uses weights
+    pub group_weight: Option<f64>,
+}
+
+/// A submission as returned by Canvas. There is one for every student and
+/// assignment, even if the student has not submitted anything.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub id: u64,
+    pub assignment_id: u64,
+    pub user_id: u64,
+    pub score: Option<f64>,
+    pub grade: Option<String>,
+
+    /// `unsubmitted`, `submitted`, `graded` or `pending_review`
+    pub workflow_state: String,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub graded_at: Option<DateTime<Utc>>,
+
+    #[serde(default, deserialize_with = "null_as_false")]
+    pub late: bool,
+
+    #[serde(default, deserialize_with = "null_as_false")]
+    pub missing: bool,
+
+    /// Canvas returns `null` instead of `false` for most submissions
+    #[serde(default, deserialize_with = "null_as_false")]
+    pub excused: bool,
+
+    /// The assignment. Always present in [`list_submissions`]
+    pub assignment: Option<Assignment>,
+}
+
+/// Returns an iterator over the assignments of a course
+pub fn list_assignments<'a>(
+    api: &'a CanvasApi,
+    course: &CanvasId,
+) -> TryItemIterator<'a, Assignment> {
+    api.get_paginated(&format!(
+        "/courses/{}/assignments?per_page=100",
+        course.course()
+    ))
+    .try_items()
+}
+
+/// Returns an iterator over the assignment groups of a course
+pub fn list_assignment_groups<'a>(
+    api: &'a CanvasApi,
+    course: &CanvasId,
+) -> TryItemIterator<'a, AssignmentGroup> {
+    api.get_paginated(&format!(
+        "/courses/{}/assignment_groups?per_page=100",
+        course.course()
+    ))
+    .try_items()
+}
+
+/// Returns an iterator over the submissions of all students and all
+/// assignments in a course. Every submission includes its assignment.
+pub fn list_submissions<'a>(
+    api: &'a CanvasApi,
+    course: &CanvasId,
+) -> TryItemIterator<'a, Submission> {
+    api.get_paginated(&format!(
+        "/courses/{}/students/submissions?{}",
+        course.course(),
+        SUBMISSIONS_QUERY
+    ))
+    .try_items()
+}
+
+/// Same as [`list_submissions`] but only for the students in a section. Use
+/// it to export grades of a section that is cross-listed.
+pub fn list_section_submissions<'a>(
+    api: &'a CanvasApi,
+    section: &CanvasId,
+) -> TryItemIterator<'a, Submission> {
+    api.get_paginated(&format!(
+        "/sections/{}/students/submissions?{}",
+        section.section(),
+        SUBMISSIONS_QUERY
+    ))
+    .try_items()
+}
+
+const SUBMISSIONS_QUERY: &str = "student_ids[]=all&include[]=assignment&per_page=100";
+
+/// Reads a boolean that can be `null`, as `false`
+fn null_as_false<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(Option::<bool>::deserialize(deserializer)?.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Starts a server that responds "404 Not Found" once and returns its URL
+    fn not_found_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/v1", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let body = r#"{"errors":[{"message":"The specified resource does not exist."}]}"#;
+            write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        url
+    }
+
+    #[test]
+    fn returns_errors_of_unknown_courses() {
+        let url = not_found_server();
+        let api = CanvasApi::new(&url, "XXXX");
+        let mut submissions = list_submissions(&api, &CanvasId::sis("XX0000HT201"));
+
+        match submissions.next() {
+            Some(Err(crate::Error::Http(e))) => assert_eq!(e.status().unwrap(), 404),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(submissions.next().is_none());
+    }
+
+    #[test]
+    fn reads_submissions_with_their_assignment() {
+        let submission: Submission = serde_json::from_str(
+            r#"{
+                "id": 1,
+                "assignment_id": 10,
+                "user_id": 100,
+                "score": 7.5,
+                "grade": "7.5",
+                "workflow_state": "graded",
+                "submitted_at": "2021-03-15T10:00:00Z",
+                "graded_at": null,
+                "late": true,
+                "excused": null,
+                "assignment": {
+                    "id": 10,
+                    "name": "Lab 1",
+                    "course_id": 5,
+                    "assignment_group_id": 3,
+                    "points_possible": 10.0,
+                    "due_at": null
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(submission.score, Some(7.5));
+        assert!(submission.late);
+        assert!(!submission.excused);
+        assert_eq!(submission.assignment.unwrap().name, "Lab 1");
+    }
+}
@@ -0,0 +1,449 @@
+//! Idempotent synchronization of the enrollments of a section
+//!
+//! Declare which users (and with which roles) should be enrolled in a section
+//! and get a [`SyncPlan`] with the actions that make Canvas match it. The
+//! plan can be printed, exported as a SIS Import CSV or executed. Running the
+//! same sync twice gives an empty plan the second time.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use canvas_api::sync::{DesiredEnrollment, RemovalPolicy, SyncPlan};
+//! use canvas_api::CanvasApi;
+//!
+//! let api = CanvasApi::new("https://kth.test.instructure.com/api/v1", "XXXX").dry_run(true);
+//! let desired = vec![
+//!     DesiredEnrollment::new("u1abcdef", "StudentEnrollment"),
+//!     DesiredEnrollment::new("u1ghijkl", "TeacherEnrollment"),
+//! ];
+//!
+//! let plan = SyncPlan::fetch(&api, "AKT.1234", &desired, RemovalPolicy::Conclude).unwrap();
+//! println!("{}", plan);
+//!
+//! for action in plan.execute(&api).unwrap() {
+//!     println!("Done: {}", action);
+//! }
+//! ```
+use crate::CanvasApi;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+
+/// Enrollment states that are requested to Canvas. Deleted enrollments are
+/// not included: they are treated as if they did not exist.
+const STATES: [&str; 5] = [
+    "active",
+    "invited",
+    "creation_pending",
+    "inactive",
+    "completed",
+];
+
+/// An enrollment as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Enrollment {
+    pub id: u64,
+    pub course_id: u64,
+    pub course_section_id: u64,
+    pub user_id: u64,
+    pub sis_user_id: Option<String>,
+    pub sis_course_id: Option<String>,
+    pub sis_section_id: Option<String>,
+
+    /// Name of the role, like `StudentEnrollment` or a custom role
+    pub role: String,
+    pub enrollment_state: String,
+}
+
+/// An enrollment that should exist in the section
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DesiredEnrollment {
+    pub sis_user_id: String,
+
+    /// Name of the role, like `StudentEnrollment` or a custom role
+    pub role: String,
+
+    /// ID of the role. Required to create enrollments with custom roles
+    pub role_id: Option<u64>,
+}
+
+/// What to do with enrollments that should not exist
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemovalPolicy {
+    /// Conclude them. Users keep read-only access to the course
+    Conclude,
+
+    /// Delete them
+    Delete,
+}
+
+/// A change to perform in Canvas
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Create a new enrollment
+    Enroll(DesiredEnrollment),
+
+    /// Activate an inactive enrollment
+    Reactivate(Enrollment),
+
+    /// Conclude an enrollment
+    Conclude(Enrollment),
+
+    /// Delete an enrollment
+    Delete(Enrollment),
+}
+
+/// Actions needed to make a section contain exactly the desired enrollments
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan {
+    pub sis_section_id: String,
+    pub actions: Vec<Action>,
+}
+
+/// Row of a SIS Import `enrollments.csv` file
+#[derive(Serialize)]
+struct SisRow<'a> {
+    course_id: &'a str,
+    user_id: &'a str,
+    role: &'a str,
+    role_id: Option<u64>,
+    section_id: &'a str,
+    status: &'a str,
+}
+
+impl DesiredEnrollment {
+    pub fn new(sis_user_id: &str, role: &str) -> DesiredEnrollment {
+        DesiredEnrollment {
+            sis_user_id: sis_user_id.to_string(),
+            role: role.to_string(),
+            role_id: None,
+        }
+    }
+
+    fn matches(&self, enrollment: &Enrollment) -> bool {
+        enrollment.sis_user_id.as_deref() == Some(self.sis_user_id.as_str())
+            && enrollment.role == self.role
+    }
+}
+
+impl Action {
+    /// Performs the action in Canvas
+    pub fn execute(&self, api: &CanvasApi, sis_section_id: &str) -> Result<(), crate::Error> {
+        let response = match self {
+            Action::Enroll(desired) => {
+                let mut enrollment = serde_json::json!({
+                    "user_id": format!("sis_user_id:{}", desired.sis_user_id),
+                    "enrollment_state": "active",
+                    "notify": false,
+                });
+
+                match desired.role_id {
+                    Some(role_id) => enrollment["role_id"] = role_id.into(),
+                    None => enrollment["type"] = desired.role.clone().into(),
+                }
+
+                api.post(
+                    &format!("/sections/sis_section_id:{}/enrollments", sis_section_id),
+                    &serde_json::json!({ "enrollment": enrollment }),
+                )?
+            }
+            Action::Reactivate(e) => api.request::<()>(
+                Method::PUT,
+                &format!("/courses/{}/enrollments/{}/reactivate", e.course_id, e.id),
+                None,
+            )?,
+            Action::Conclude(e) => api.delete(&format!(
+                "/courses/{}/enrollments/{}?task=conclude",
+                e.course_id, e.id
+            ))?,
+            Action::Delete(e) => api.delete(&format!(
+                "/courses/{}/enrollments/{}?task=delete",
+                e.course_id, e.id
+            ))?,
+        };
+
+        response.error_for_status()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let user = |e: &Enrollment| {
+            e.sis_user_id
+                .clone()
+                .unwrap_or_else(|| format!("user {}", e.user_id))
+        };
+
+        match self {
+            Action::Enroll(d) => write!(f, "+ enroll     {} as {}", d.sis_user_id, d.role),
+            Action::Reactivate(e) => write!(f, "~ reactivate {} as {}", user(e), e.role),
+            Action::Conclude(e) => write!(f, "- conclude   {} as {}", user(e), e.role),
+            Action::Delete(e) => write!(f, "- delete     {} as {}", user(e), e.role),
+        }
+    }
+}
+
+impl SyncPlan {
+    /// Computes the plan from the current enrollments of the section.
+    ///
+    /// - Desired enrollments that do not exist (or are concluded) are
+    ///   enrolled
+    /// - Desired enrollments that are inactive are reactivated
+    /// - Current enrollments that are not desired are concluded or deleted,
+    ///   according to `policy`
+    pub fn new(
+        sis_section_id: &str,
+        current: &[Enrollment],
+        desired: &[DesiredEnrollment],
+        policy: RemovalPolicy,
+    ) -> SyncPlan {
+        let mut actions = vec![];
+        let mut seen = HashSet::new();
+
+        for d in desired.iter().filter(|d| seen.insert(*d)) {
+            let mut existing = current.iter().filter(|e| d.matches(e));
+
+            if existing
+                .clone()
+                .any(|e| e.enrollment_state != "completed" && e.enrollment_state != "inactive")
+            {
+                continue;
+            }
+
+            match existing.find(|e| e.enrollment_state == "inactive") {
+                Some(inactive) => actions.push(Action::Reactivate(inactive.clone())),
+                None => actions.push(Action::Enroll(d.clone())),
+            }
+        }
+
+        for enrollment in current {
+            let is_desired = desired.iter().any(|d| d.matches(enrollment));
+
+            if is_desired || enrollment.enrollment_state == "completed" {
+                continue;
+            }
+
+            actions.push(match policy {
+                RemovalPolicy::Conclude => Action::Conclude(enrollment.clone()),
+                RemovalPolicy::Delete => Action::Delete(enrollment.clone()),
+            });
+        }
+
+        SyncPlan {
+            sis_section_id: sis_section_id.to_string(),
+            actions,
+        }
+    }
+
+    /// Fetches the current enrollments of a section and computes the plan.
+    /// See [`SyncPlan::new`].
+    pub fn fetch(
+        api: &CanvasApi,
+        sis_section_id: &str,
+        desired: &[DesiredEnrollment],
+        policy: RemovalPolicy,
+    ) -> Result<SyncPlan, crate::Error> {
+        let current = fetch_enrollments(api, sis_section_id)?;
+
+        Ok(SyncPlan::new(sis_section_id, &current, desired, policy))
+    }
+
+    /// Returns `true` if the section already matches the desired state
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Performs all the actions in order and returns them. Stops at the
+    /// first failure and returns the action that failed: the ones before it
+    /// were performed. Since the sync is idempotent, computing and executing
+    /// a new plan continues where this one stopped.
+    pub fn execute(&self, api: &CanvasApi) -> Result<&[Action], (&Action, crate::Error)> {
+        for action in &self.actions {
+            action
+                .execute(api, &self.sis_section_id)
+                .map_err(|e| (action, e))?;
+        }
+
+        Ok(&self.actions)
+    }
+
+    /// Writes the plan as a SIS Import `enrollments.csv` file. Enrollments of
+    /// users without SIS ID cannot be written and are skipped.
+    pub fn write_sis_csv<W: io::Write>(&self, writer: W) -> Result<(), csv::Error> {
+        let mut wtr = csv::Writer::from_writer(writer);
+
+        for action in &self.actions {
+            let (enrollment, status) = match action {
+                Action::Enroll(d) => {
+                    wtr.serialize(SisRow {
+                        course_id: "",
+                        user_id: &d.sis_user_id,
+                        role: sis_role(&d.role),
+                        role_id: d.role_id,
+                        section_id: &self.sis_section_id,
+                        status: "active",
+                    })?;
+                    continue;
+                }
+                Action::Reactivate(e) => (e, "active"),
+                Action::Conclude(e) => (e, "completed"),
+                Action::Delete(e) => (e, "deleted"),
+            };
+
+            let user_id = match &enrollment.sis_user_id {
+                None => continue,
+                Some(user_id) => user_id,
+            };
+
+            wtr.serialize(SisRow {
+                course_id: enrollment.sis_course_id.as_deref().unwrap_or(""),
+                user_id,
+                role: sis_role(&enrollment.role),
+                role_id: None,
+                section_id: &self.sis_section_id,
+                status,
+            })?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Section {}: nothing to do", self.sis_section_id);
+        }
+
+        write!(
+            f,
+            "Section {}: {} actions",
+            self.sis_section_id,
+            self.actions.len()
+        )?;
+
+        for action in &self.actions {
+            write!(f, "\n  {}", action)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetches the enrollments of a section in any state except "deleted"
+pub fn fetch_enrollments(
+    api: &CanvasApi,
+    sis_section_id: &str,
+) -> Result<Vec<Enrollment>, crate::Error> {
+    let states: String = STATES.iter().map(|s| format!("&state[]={}", s)).collect();
+    let pages = api.get_paginated(&format!(
+        "/sections/sis_section_id:{}/enrollments?per_page=100{}",
+        sis_section_id, states
+    ));
+
+    let mut enrollments = vec![];
+
+    for page in pages {
+        enrollments.append(&mut page?.error_for_status()?.json::<Vec<Enrollment>>()?);
+    }
+
+    Ok(enrollments)
+}
+
+/// Returns the name of a role as written in SIS Import files
+fn sis_role(role: &str) -> &str {
+    match role {
+        "StudentEnrollment" => "student",
+        "TeacherEnrollment" => "teacher",
+        "TaEnrollment" => "ta",
+        "ObserverEnrollment" => "observer",
+        "DesignerEnrollment" => "designer",
+        custom => custom,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enrollment(id: u64, user: &str, role: &str, state: &str) -> Enrollment {
+        Enrollment {
+            id,
+            course_id: 1,
+            course_section_id: 2,
+            user_id: id + 100,
+            sis_user_id: Some(user.to_string()),
+            sis_course_id: Some("SF1624VT211".to_string()),
+            sis_section_id: Some("S".to_string()),
+            role: role.to_string(),
+            enrollment_state: state.to_string(),
+        }
+    }
+
+    #[test]
+    fn plans_the_difference() {
+        let current = vec![
+            enrollment(1, "keep", "StudentEnrollment", "active"),
+            enrollment(2, "remove", "StudentEnrollment", "active"),
+            enrollment(3, "inactive", "StudentEnrollment", "inactive"),
+            enrollment(4, "concluded", "StudentEnrollment", "completed"),
+            enrollment(5, "role", "StudentEnrollment", "active"),
+        ];
+        let desired = vec![
+            DesiredEnrollment::new("keep", "StudentEnrollment"),
+            DesiredEnrollment::new("inactive", "StudentEnrollment"),
+            DesiredEnrollment::new("concluded", "StudentEnrollment"),
+            DesiredEnrollment::new("role", "TeacherEnrollment"),
+            DesiredEnrollment::new("new", "StudentEnrollment"),
+            DesiredEnrollment::new("new", "StudentEnrollment"),
+        ];
+
+        let plan = SyncPlan::new("S", &current, &desired, RemovalPolicy::Conclude);
+
+        assert_eq!(
+            plan.actions,
+            vec![
+                Action::Reactivate(current[2].clone()),
+                Action::Enroll(desired[2].clone()),
+                Action::Enroll(desired[3].clone()),
+                Action::Enroll(desired[4].clone()),
+                Action::Conclude(current[1].clone()),
+                Action::Conclude(current[4].clone()),
+            ]
+        );
+
+        let mut csv = vec![];
+        plan.write_sis_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert!(csv.starts_with("course_id,user_id,role,role_id,section_id,status\n"));
+        assert!(csv.contains("SF1624VT211,remove,student,,S,completed\n"));
+        assert!(csv.contains(",new,student,,S,active\n"));
+    }
+
+    #[test]
+    fn synced_section_has_empty_plan() {
+        let current = vec![enrollment(1, "keep", "TeacherEnrollment", "invited")];
+        let desired = vec![DesiredEnrollment::new("keep", "TeacherEnrollment")];
+
+        assert!(SyncPlan::new("S", &current, &desired, RemovalPolicy::Delete).is_empty());
+    }
+
+    #[test]
+    fn returns_the_executed_actions() {
+        // The URL is not reachable: only dry-run requests succeed
+        let current = vec![enrollment(1, "remove", "StudentEnrollment", "active")];
+        let desired = vec![DesiredEnrollment::new("new", "StudentEnrollment")];
+        let plan = SyncPlan::new("S", &current, &desired, RemovalPolicy::Delete);
+
+        let api = CanvasApi::new("http://127.0.0.1:9", "XXXX");
+        let (failed, _) = plan.execute(&api).unwrap_err();
+        assert_eq!(failed, &plan.actions[0]);
+
+        assert_eq!(plan.execute(&api.dry_run(true)).unwrap(), &plan.actions[..]);
+    }
+}
@@ -0,0 +1,183 @@
+//! Enrollment terms and their relation with KTH semesters
+//!
+//! [`Semester`] and [`Term`] come from the `kth_terms` crate and are
+//! re-exported here. [`TermMapping`] finds the Canvas enrollment term of a
+//! semester and vice versa.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use canvas_api::courses::CanvasId;
+//! use canvas_api::terms::{self, Semester, TermMapping};
+//! use canvas_api::CanvasApi;
+//!
+//! let api = CanvasApi::new("https://kth.test.instructure.com/api/v1", "XXXX");
+//! let mapping = TermMapping::new(terms::list_terms(&api, &CanvasId::Id(1)).unwrap());
+//! let semester: Semester = "20201".parse().unwrap();
+//!
+//! match mapping.term_for(&semester) {
+//!     Some(term) => println!("{} is the Canvas term {}", semester, term.name),
+//!     None => println!("There is no Canvas term for {}", semester),
+//! }
+//! ```
+use crate::courses::CanvasId;
+use crate::CanvasApi;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+
+pub use kth_terms::{Semester, Term};
+
+/// An enrollment term as returned by Canvas
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EnrollmentTerm {
+    pub id: u64,
+    pub name: String,
+    pub sis_term_id: Option<String>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub workflow_state: Option<String>,
+}
+
+/// Finds Canvas terms for semesters and vice versa
+#[derive(Debug, Clone)]
+pub struct TermMapping {
+    terms: Vec<EnrollmentTerm>,
+}
+
+#[derive(Deserialize)]
+struct TermsPage {
+    enrollment_terms: Vec<EnrollmentTerm>,
+}
+
+impl EnrollmentTerm {
+    /// Returns `true` if `date` is between the start and the end of the term.
+    /// Terms without dates contain no date.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match (self.start_at, self.end_at) {
+            (Some(start), Some(end)) => {
+                start.naive_utc().date() <= date && date <= end.naive_utc().date()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl TermMapping {
+    pub fn new(terms: Vec<EnrollmentTerm>) -> TermMapping {
+        TermMapping { terms }
+    }
+
+    pub fn terms(&self) -> &[EnrollmentTerm] {
+        &self.terms
+    }
+
+    /// Finds a term by its SIS ID
+    pub fn by_sis_id(&self, sis_term_id: &str) -> Option<&EnrollmentTerm> {
+        self.terms
+            .iter()
+            .find(|t| t.sis_term_id.as_deref() == Some(sis_term_id))
+    }
+
+    /// Finds the term that contains a date. If several terms contain it, the
+    /// one that started last is returned.
+    pub fn by_date(&self, date: NaiveDate) -> Option<&EnrollmentTerm> {
+        self.terms
+            .iter()
+            .filter(|t| t.contains(date))
+            .max_by_key(|t| t.start_at)
+    }
+
+    /// Finds the term of a semester: the one with its Kopps or Ladok code as
+    /// SIS ID or, if there is none, the one that contains the middle of the
+    /// semester.
+    pub fn term_for(&self, semester: &Semester) -> Option<&EnrollmentTerm> {
+        self.terms
+            .iter()
+            .find(|t| {
+                t.sis_term_id
+                    .as_deref()
+                    .and_then(|id| id.parse::<Semester>().ok())
+                    == Some(*semester)
+            })
+            .or_else(|| self.by_date(semester.middle()))
+    }
+
+    /// Returns the semester of a term, read from its SIS ID or its start date
+    pub fn semester_for(&self, term: &EnrollmentTerm) -> Option<Semester> {
+        term.sis_term_id
+            .as_deref()
+            .and_then(|id| id.parse().ok())
+            .or_else(|| {
+                term.start_at
+                    .map(|start| Semester::containing(start.naive_utc().date()))
+            })
+    }
+}
+
+/// Lists the enrollment terms of an account
+pub fn list_terms(
+    api: &CanvasApi,
+    account: &CanvasId,
+) -> Result<Vec<EnrollmentTerm>, crate::Error> {
+    let mut terms = vec![];
+
+    // This endpoint returns an object with the list inside, so ItemIterator
+    // cannot be used
+    for page in api.get_paginated(&format!(
+        "/accounts/{}/terms?per_page=100",
+        account.account()
+    )) {
+        terms.append(
+            &mut page?
+                .error_for_status()?
+                .json::<TermsPage>()?
+                .enrollment_terms,
+        );
+    }
+
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn term(
+        id: u64,
+        sis_term_id: Option<&str>,
+        start: (i32, u32, u32),
+        end: (i32, u32, u32),
+    ) -> EnrollmentTerm {
+        EnrollmentTerm {
+            id,
+            name: format!("Term {}", id),
+            sis_term_id: sis_term_id.map(String::from),
+            start_at: Some(Utc.ymd(start.0, start.1, start.2).and_hms(0, 0, 0)),
+            end_at: Some(Utc.ymd(end.0, end.1, end.2).and_hms(0, 0, 0)),
+            workflow_state: Some("active".to_string()),
+        }
+    }
+
+    #[test]
+    fn maps_semesters_and_terms() {
+        let mapping = TermMapping::new(vec![
+            term(1, Some("VT2020"), (2020, 1, 15), (2020, 6, 10)),
+            term(2, None, (2020, 8, 20), (2021, 1, 15)),
+        ]);
+
+        let vt2020 = Semester::new(2020, Term::Spring);
+        let ht2020 = Semester::new(2020, Term::Autumn);
+
+        assert_eq!(mapping.term_for(&vt2020).unwrap().id, 1);
+        assert_eq!(mapping.term_for(&ht2020).unwrap().id, 2);
+        assert!(mapping
+            .term_for(&Semester::new(2021, Term::Spring))
+            .is_none());
+        assert_eq!(mapping.semester_for(&mapping.terms()[1]), Some(ht2020));
+        assert_eq!(
+            mapping.by_date(NaiveDate::from_ymd(2020, 3, 1)).unwrap().id,
+            1
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Access tokens and the places where they can be read from
+//!
+//! Tokens should not be written in plaintext files. Instead, you can obtain
+//! them from:
+//!
+//! - an external command, like the CLI of a password manager
+//! - a file that only its owner can read
+//! - the OS keyring (Secret Service), through `secret-tool`
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An access token. `Debug` and `Display` never show its value: call
+/// [`Token::expose`] to read it.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Token(String);
+
+/// Where to obtain an access token from
+#[derive(Clone, Debug)]
+pub enum TokenSource {
+    /// The token itself
+    Literal(Token),
+
+    /// Shell command that prints the token
+    Command(String),
+
+    /// File that contains the token. In Unix, the file must not be readable
+    /// by other users than its owner (like `chmod 600`)
+    File(PathBuf),
+
+    /// Entry in the OS keyring
+    Keyring(KeyringEntry),
+}
+
+/// Attributes that identify a token in the OS keyring
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct KeyringEntry {
+    pub service: String,
+    pub account: String,
+}
+
+/// A store of secrets, like the OS keyring
+pub trait SecretStore {
+    /// Returns the secret stored with the given attributes, if any
+    fn lookup(&self, entry: &KeyringEntry) -> Result<Option<String>, String>;
+}
+
+/// The Secret Service (GNOME Keyring, KWallet...) accessed through the
+/// `secret-tool` command. Store a token with:
+///
+/// ```sh
+/// secret-tool store --label "Canvas" service canvas account prod
+/// ```
+pub struct SecretTool;
+
+/// Errors that can happen while obtaining a token
+#[derive(Debug)]
+pub enum TokenError {
+    /// The command could not run or exited with an error
+    Command(String),
+
+    /// The file could not be read
+    File(PathBuf, io::Error),
+
+    /// The file can be read by other users
+    InsecureFile(PathBuf),
+
+    /// The keyring could not be read
+    Keyring(String),
+
+    /// The source returned an empty token
+    Empty,
+}
+
+impl Token {
+    pub fn new<S: Into<String>>(token: S) -> Token {
+        Token(token.into())
+    }
+
+    /// Returns the token in plaintext. Do not print it!
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Token(***)")
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenError::Command(e) => write!(f, "Token command failed: {}", e),
+            TokenError::File(path, e) => write!(f, "Cannot read `{}`: {}", path.display(), e),
+            TokenError::InsecureFile(path) => write!(
+                f,
+                "`{}` can be read by other users. Run `chmod 600` on it",
+                path.display()
+            ),
+            TokenError::Keyring(e) => write!(f, "Cannot read the keyring: {}", e),
+            TokenError::Empty => write!(f, "The token is empty"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+impl TokenSource {
+    /// Reads the source from environmental variables. The first one that is
+    /// set is used:
+    ///
+    /// - `CANVAS_API_TOKEN`: the token itself
+    /// - `CANVAS_API_TOKEN_COMMAND`: a command that prints the token
+    /// - `CANVAS_API_TOKEN_FILE`: a file with the token
+    pub fn from_env() -> Option<TokenSource> {
+        if let Ok(token) = env::var("CANVAS_API_TOKEN") {
+            return Some(TokenSource::Literal(Token::new(token)));
+        }
+
+        if let Ok(command) = env::var("CANVAS_API_TOKEN_COMMAND") {
+            return Some(TokenSource::Command(command));
+        }
+
+        env::var("CANVAS_API_TOKEN_FILE")
+            .ok()
+            .map(|path| TokenSource::File(PathBuf::from(path)))
+    }
+
+    /// Obtains the token. Keyring entries are read with [`SecretTool`]
+    pub fn resolve(&self) -> Result<Token, TokenError> {
+        self.resolve_with(&SecretTool)
+    }
+
+    /// Obtains the token, reading keyring entries from `store`
+    pub fn resolve_with(&self, store: &dyn SecretStore) -> Result<Token, TokenError> {
+        let token = match self {
+            TokenSource::Literal(token) => token.expose().to_string(),
+            TokenSource::Command(command) => run_command(command)?,
+            TokenSource::File(path) => read_private_file(path)?,
+            TokenSource::Keyring(entry) => store
+                .lookup(entry)
+                .map_err(TokenError::Keyring)?
+                .ok_or_else(|| {
+                    TokenError::Keyring(format!(
+                        "No entry with service `{}` and account `{}`",
+                        entry.service, entry.account
+                    ))
+                })?,
+        };
+
+        match token.trim() {
+            "" => Err(TokenError::Empty),
+            token => Ok(Token::new(token)),
+        }
+    }
+}
+
+impl SecretStore for SecretTool {
+    fn lookup(&self, entry: &KeyringEntry) -> Result<Option<String>, String> {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", &entry.service])
+            .args(["account", &entry.account])
+            .output()
+            .map_err(|e| format!("cannot run `secret-tool`: {}", e))?;
+
+        // `secret-tool lookup` exits with 1 when there is no such entry
+        match output.status.success() {
+            true => Ok(Some(String::from_utf8_lossy(&output.stdout).to_string())),
+            false => Ok(None),
+        }
+    }
+}
+
+fn run_command(command: &str) -> Result<String, TokenError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| TokenError::Command(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(TokenError::Command(format!(
+            "exited with {}",
+            output.status
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn read_private_file(path: &Path) -> Result<String, TokenError> {
+    let metadata = fs::metadata(path).map_err(|e| TokenError::File(path.to_path_buf(), e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if metadata.permissions().mode() & 0o077 != 0 {
+            return Err(TokenError::InsecureFile(path.to_path_buf()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    fs::read_to_string(path).map_err(|e| TokenError::File(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeKeyring;
+
+    impl SecretStore for FakeKeyring {
+        fn lookup(&self, entry: &KeyringEntry) -> Result<Option<String>, String> {
+            match entry.account.as_str() {
+                "prod" => Ok(Some("from-keyring\n".to_string())),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn token_is_not_printed() {
+        let token = Token::new("secret");
+
+        assert_eq!(format!("{:?}", token), "Token(***)");
+        assert_eq!(format!("{}", token), "***");
+        assert_eq!(token.expose(), "secret");
+    }
+
+    #[test]
+    fn resolves_sources() {
+        let command = TokenSource::Command("echo from-command".to_string());
+        assert_eq!(command.resolve().unwrap().expose(), "from-command");
+
+        let failing = TokenSource::Command("exit 1".to_string());
+        assert!(failing.resolve().is_err());
+
+        let keyring = |account: &str| {
+            TokenSource::Keyring(KeyringEntry {
+                service: "canvas".to_string(),
+                account: account.to_string(),
+            })
+        };
+        assert_eq!(
+            keyring("prod").resolve_with(&FakeKeyring).unwrap().expose(),
+            "from-keyring"
+        );
+        assert!(keyring("beta").resolve_with(&FakeKeyring).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_files_readable_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join(format!("canvas-token-{}", std::process::id()));
+        fs::write(&path, "from-file\n").unwrap();
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(TokenSource::File(path.clone()).resolve().is_err());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(
+            TokenSource::File(path.clone()).resolve().unwrap().expose(),
+            "from-file"
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,718 @@
+//! Client for Canvas Data 2 (the Data Access Platform, DAP)
+//!
+//! Canvas Data gives whole tables (all users, all enrollments...) as
+//! compressed files instead of thousands of REST requests. Getting a table
+//! works like this:
+//!
+//! 1. Log in with the client ID and secret of the Canvas Data credentials
+//! 2. Start a query job for a table: a **snapshot** (the whole table) or an
+//!    **incremental** query (changes since a time)
+//! 3. Wait for the job to complete
+//! 4. Get the URLs of the files produced by the job and download them. Files
+//!    are gzip-compressed JSON lines
+//!
+//! [`DapClient::rows`] does all of it and returns an iterator of typed rows
+//! (see the [`tables`] module). The files are read while iterating, so big
+//! tables are never loaded in memory at once.
+//!
+//! Example: count active users
+//!
+//! ```no_run
+//! use canvas_data::tables::User;
+//! use canvas_data::{DapClient, Query};
+//!
+//! let mut client = DapClient::from_env().expect("DAP_CLIENT_ID and DAP_CLIENT_SECRET not set");
+//! let active = client
+//!     .rows::<User>(&Query::Snapshot)
+//!     .unwrap()
+//!     .filter_map(|record| record.unwrap().value)
+//!     .filter(|user| user.workflow_state == "registered")
+//!     .count();
+//!
+//! println!("{} active users", active);
+//! ```
+pub mod tables;
+
+use canvas_api::Token;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Lines, Read};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tables::{Record, Table};
+
+/// URL of the Instructure API gateway
+pub const DEFAULT_URL: &str = "https://api-gateway.instructure.com";
+
+/// Namespace of the Canvas tables
+const NAMESPACE: &str = "canvas";
+
+/// Access tokens are renewed when they expire in less than this time
+const RENEW_MARGIN: Duration = Duration::from_secs(60);
+
+/// Client ID and secret of the Canvas Data credentials
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: Token,
+}
+
+/// A client of the Data Access Platform. It logs in when needed.
+pub struct DapClient {
+    base_url: String,
+    credentials: Credentials,
+    http: Client,
+    token: Option<(Token, Instant)>,
+    poll_interval: Duration,
+    job_timeout: Duration,
+}
+
+/// What to get from a table
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// The whole table
+    Snapshot,
+
+    /// The rows that changed since a time. Deleted rows are included
+    Incremental { since: DateTime<Utc> },
+}
+
+/// A query job
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+
+    /// Files produced by the job. Only present when it is complete
+    #[serde(default)]
+    pub objects: Vec<Object>,
+
+    /// Time of the data in a snapshot
+    pub at: Option<DateTime<Utc>>,
+
+    /// Time range of the data in an incremental query
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub error: Option<JobError>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Waiting,
+    Running,
+    Complete,
+    Failed,
+}
+
+/// A file produced by a job
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Object {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct JobError {
+    pub message: String,
+}
+
+/// Iterator over the rows of one or more downloaded files. Files are opened
+/// one after the other when the previous one is finished.
+pub struct RowIterator<T> {
+    http: Client,
+    sources: VecDeque<Source>,
+    lines: Option<FileLines>,
+    row: PhantomData<T>,
+}
+
+/// Lines of the file that is being read
+type FileLines = Lines<BufReader<GzDecoder<Box<dyn Read>>>>;
+
+enum Source {
+    Url(String),
+    File(PathBuf),
+}
+
+/// Errors that can happen when getting data
+#[derive(Debug)]
+pub enum DataError {
+    /// The request could not be performed
+    Http(reqwest::Error),
+
+    /// The API responded with an error
+    Rejected(u16, String),
+
+    /// The query job failed
+    JobFailed(String, String),
+
+    /// The query job was not complete after the maximum wait
+    JobTimedOut(String, Duration),
+
+    /// A file could not be read or written
+    Io(io::Error),
+
+    /// A row could not be parsed
+    Json(serde_json::Error),
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct TablesResponse {
+    tables: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct QueryBody {
+    format: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct UrlsResponse {
+    urls: HashMap<String, ObjectUrl>,
+}
+
+#[derive(Deserialize)]
+struct ObjectUrl {
+    url: String,
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Http(e) => write!(f, "Request to Canvas Data failed: {}", e),
+            DataError::Rejected(status, body) => {
+                write!(f, "Canvas Data responded {}: {}", status, body)
+            }
+            DataError::JobFailed(id, message) => write!(f, "Job {} failed: {}", id, message),
+            DataError::JobTimedOut(id, timeout) => write!(
+                f,
+                "Job {} was not complete after {} seconds",
+                id,
+                timeout.as_secs()
+            ),
+            DataError::Io(e) => write!(f, "Cannot read or write a file: {}", e),
+            DataError::Json(e) => write!(f, "Cannot parse a row: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<reqwest::Error> for DataError {
+    fn from(e: reqwest::Error) -> DataError {
+        DataError::Http(e)
+    }
+}
+
+impl From<io::Error> for DataError {
+    fn from(e: io::Error) -> DataError {
+        DataError::Io(e)
+    }
+}
+
+impl DapClient {
+    /// Creates a client. `base_url` is usually [`DEFAULT_URL`]
+    pub fn new(base_url: &str, credentials: Credentials) -> DapClient {
+        DapClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials,
+            http: Client::new(),
+            token: None,
+            poll_interval: Duration::from_secs(5),
+            job_timeout: Duration::from_secs(3600),
+        }
+    }
+
+    /// Creates a client from the `DAP_CLIENT_ID` and `DAP_CLIENT_SECRET`
+    /// environmental variables, and optionally `DAP_API_URL`
+    pub fn from_env() -> Option<DapClient> {
+        let credentials = Credentials {
+            client_id: env::var("DAP_CLIENT_ID").ok()?,
+            client_secret: Token::new(env::var("DAP_CLIENT_SECRET").ok()?),
+        };
+        let base_url = env::var("DAP_API_URL").unwrap_or_else(|_| DEFAULT_URL.to_string());
+
+        Some(DapClient::new(&base_url, credentials))
+    }
+
+    /// Sets how often to check if a job is complete. Default: 5 seconds
+    pub fn poll_interval(mut self, poll_interval: Duration) -> DapClient {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the maximum time to wait for a job to be complete. Default: 1 hour
+    pub fn job_timeout(mut self, job_timeout: Duration) -> DapClient {
+        self.job_timeout = job_timeout;
+        self
+    }
+
+    /// Lists the names of the tables that can be queried
+    pub fn list_tables(&mut self) -> Result<Vec<String>, DataError> {
+        let url = format!("{}/dap/query/{}/table", self.base_url, NAMESPACE);
+        let response: TablesResponse = read_json(self.authorized(Method::GET, &url)?.send()?)?;
+
+        Ok(response.tables)
+    }
+
+    /// Starts a query job for a table
+    pub fn start_job(&mut self, table: &str, query: &Query) -> Result<Job, DataError> {
+        let url = format!(
+            "{}/dap/query/{}/table/{}/data",
+            self.base_url, NAMESPACE, table
+        );
+        let body = QueryBody {
+            format: "jsonl",
+            since: match query {
+                Query::Snapshot => None,
+                Query::Incremental { since } => Some(*since),
+            },
+        };
+
+        read_json(self.authorized(Method::POST, &url)?.json(&body).send()?)
+    }
+
+    /// Gets the current state of a job
+    pub fn job(&mut self, job_id: &str) -> Result<Job, DataError> {
+        let url = format!("{}/dap/job/{}", self.base_url, job_id);
+
+        read_json(self.authorized(Method::GET, &url)?.send()?)
+    }
+
+    /// Waits until a job is complete, at most the time set with
+    /// [`DapClient::job_timeout`]
+    pub fn wait(&mut self, mut job: Job) -> Result<Job, DataError> {
+        let deadline = Instant::now() + self.job_timeout;
+
+        loop {
+            match job.status {
+                JobStatus::Complete => return Ok(job),
+                JobStatus::Failed => {
+                    let message = job.error.map(|e| e.message).unwrap_or_default();
+                    return Err(DataError::JobFailed(job.id, message));
+                }
+                JobStatus::Waiting | JobStatus::Running if Instant::now() >= deadline => {
+                    return Err(DataError::JobTimedOut(job.id, self.job_timeout));
+                }
+                JobStatus::Waiting | JobStatus::Running => {
+                    thread::sleep(self.poll_interval);
+                    job = self.job(&job.id)?;
+                }
+            }
+        }
+    }
+
+    /// Gets the download URLs of the files of a job, in the same order
+    pub fn object_urls(&mut self, objects: &[Object]) -> Result<Vec<String>, DataError> {
+        let url = format!("{}/dap/object/url", self.base_url);
+        let mut response: UrlsResponse =
+            read_json(self.authorized(Method::POST, &url)?.json(objects).send()?)?;
+
+        objects
+            .iter()
+            .map(|object| {
+                response
+                    .urls
+                    .remove(&object.id)
+                    .map(|o| o.url)
+                    .ok_or_else(|| {
+                        DataError::Rejected(200, format!("No URL for the object {}", object.id))
+                    })
+            })
+            .collect()
+    }
+
+    /// Runs a query job for a table and returns the URLs of its files
+    pub fn query(&mut self, table: &str, query: &Query) -> Result<Vec<String>, DataError> {
+        let job = self.start_job(table, query)?;
+        let job = self.wait(job)?;
+
+        self.object_urls(&job.objects)
+    }
+
+    /// Runs a query job for a table and saves its files (gzip-compressed JSON
+    /// lines) in `directory`. Returns the paths of the files.
+    pub fn download<P: AsRef<Path>>(
+        &mut self,
+        table: &str,
+        query: &Query,
+        directory: P,
+    ) -> Result<Vec<PathBuf>, DataError> {
+        fs::create_dir_all(&directory)?;
+        let mut paths = vec![];
+
+        for (i, url) in self.query(table, query)?.iter().enumerate() {
+            let path = directory
+                .as_ref()
+                .join(format!("{}-{}.json.gz", table, i + 1));
+            let mut response = check_status(self.http.get(url).send()?)?;
+            io::copy(&mut response, &mut File::create(&path)?)?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Runs a query job for a table and returns an iterator of its rows
+    pub fn rows<T: Table>(&mut self, query: &Query) -> Result<RowIterator<T>, DataError> {
+        let urls = self.query(T::NAME, query)?;
+
+        Ok(RowIterator {
+            http: self.http.clone(),
+            sources: urls.into_iter().map(Source::Url).collect(),
+            lines: None,
+            row: PhantomData,
+        })
+    }
+
+    fn authorized(&mut self, method: Method, url: &str) -> Result<RequestBuilder, DataError> {
+        let token = self.access_token()?;
+
+        Ok(self
+            .http
+            .request(method, url)
+            .header("x-instauth", token.expose()))
+    }
+
+    fn access_token(&mut self) -> Result<Token, DataError> {
+        if let Some((token, expires_at)) = &self.token {
+            if Instant::now() + RENEW_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let response: LoginResponse = read_json(
+            self.http
+                .post(format!("{}/ids/auth/login", self.base_url))
+                .basic_auth(
+                    &self.credentials.client_id,
+                    Some(self.credentials.client_secret.expose()),
+                )
+                .form(&[("grant_type", "client_credentials")])
+                .send()?,
+        )?;
+        let token = Token::new(response.access_token);
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+
+        self.token = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+impl<T> RowIterator<T> {
+    /// Reads rows from files that were saved with [`DapClient::download`]
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> RowIterator<T> {
+        RowIterator {
+            http: Client::new(),
+            sources: paths
+                .iter()
+                .map(|p| Source::File(p.as_ref().to_path_buf()))
+                .collect(),
+            lines: None,
+            row: PhantomData,
+        }
+    }
+
+    fn open(&self, source: &Source) -> Result<Box<dyn Read>, DataError> {
+        Ok(match source {
+            Source::Url(url) => Box::new(check_status(self.http.get(url).send()?)?),
+            Source::File(path) => Box::new(File::open(path)?),
+        })
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for RowIterator<T> {
+    type Item = Result<Record<T>, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(lines) = &mut self.lines {
+                match lines.next() {
+                    Some(Ok(line)) if line.trim().is_empty() => continue,
+                    Some(Ok(line)) => {
+                        return Some(serde_json::from_str(&line).map_err(DataError::Json))
+                    }
+                    Some(Err(e)) => {
+                        self.lines = None;
+                        return Some(Err(DataError::Io(e)));
+                    }
+                    None => self.lines = None,
+                }
+            }
+
+            let source = self.sources.pop_front()?;
+
+            match self.open(&source) {
+                Ok(reader) => self.lines = Some(BufReader::new(GzDecoder::new(reader)).lines()),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+fn check_status(response: Response) -> Result<Response, DataError> {
+    let status = response.status();
+
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(DataError::Rejected(
+            status.as_u16(),
+            response.text().unwrap_or_default(),
+        ))
+    }
+}
+
+fn read_json<T: DeserializeOwned>(response: Response) -> Result<T, DataError> {
+    Ok(check_status(response)?.json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tables::{Course, Enrollment, User};
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_json::json;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    const USERS: &str = r#"{"key":{"id":1},"value":{"name":"Ada","sortable_name":"Lovelace, Ada","workflow_state":"registered"},"meta":{"action":"U","ts":"2021-03-01T10:00:00Z"}}
+{"key":{"id":2},"value":{"name":"Alan","sortable_name":"Turing, Alan","workflow_state":"deleted"},"meta":{"action":"U","ts":"2021-03-01T10:00:00Z"}}
+"#;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Starts a server that answers like the Data Access Platform. Returns
+    /// its URL
+    fn stand_in() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let base_url = url.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                respond(stream.unwrap(), &base_url);
+            }
+        });
+
+        url
+    }
+
+    fn respond(mut stream: TcpStream, base_url: &str) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+
+        let mut content_length = 0;
+        let mut logged_in = false;
+        let mut basic_auth = false;
+
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            let header = header.trim_end().to_lowercase();
+
+            if header.is_empty() {
+                break;
+            } else if let Some(length) = header.strip_prefix("content-length:") {
+                content_length = length.trim().parse().unwrap();
+            } else if header == "x-instauth: secret-token" {
+                logged_in = true;
+            } else if header.starts_with("authorization: basic ") {
+                basic_auth = true;
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap();
+        let path = parts.next().unwrap();
+
+        let (status, response) = match (method, path) {
+            ("POST", "/ids/auth/login") if basic_auth => (
+                200,
+                json!({"access_token": "secret-token", "expires_in": 3600}).to_string(),
+            ),
+            (_, path) if path.starts_with("/dap") && !logged_in => (401, "{}".to_string()),
+            ("GET", "/dap/query/canvas/table") => (
+                200,
+                json!({"tables": ["users", "enrollments", "courses"]}).to_string(),
+            ),
+            ("POST", "/dap/query/canvas/table/users/data") => {
+                (200, json!({"id": "job-1", "status": "waiting"}).to_string())
+            }
+            ("POST", "/dap/query/canvas/table/courses/data") => {
+                (200, json!({"id": "job-2", "status": "running"}).to_string())
+            }
+            ("GET", "/dap/job/job-1") => (
+                200,
+                json!({
+                    "id": "job-1",
+                    "status": "complete",
+                    "at": "2021-03-01T12:00:00Z",
+                    "objects": [{"id": "job-1/part-1.json.gz"}]
+                })
+                .to_string(),
+            ),
+            ("POST", "/dap/query/canvas/table/enrollments/data") | ("GET", "/dap/job/job-3") => {
+                (200, json!({"id": "job-3", "status": "running"}).to_string())
+            }
+            ("GET", "/dap/job/job-2") => (
+                200,
+                json!({"id": "job-2", "status": "failed", "error": {"message": "Boom"}})
+                    .to_string(),
+            ),
+            ("POST", "/dap/object/url") => (
+                200,
+                json!({"urls": {
+                    "job-1/part-1.json.gz": {"url": format!("{}/files/part-1.json.gz", base_url)}
+                }})
+                .to_string(),
+            ),
+            ("GET", "/files/part-1.json.gz") => {
+                let data = gzip(USERS);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    data.len()
+                )
+                .unwrap();
+                stream.write_all(&data).unwrap();
+                return;
+            }
+            _ => (404, "{}".to_string()),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {} Stand-in\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            response.len(),
+            response
+        )
+        .unwrap();
+    }
+
+    fn client(url: &str) -> DapClient {
+        let credentials = Credentials {
+            client_id: "us-east-1#client".to_string(),
+            client_secret: Token::new("secret"),
+        };
+
+        DapClient::new(url, credentials).poll_interval(Duration::from_millis(10))
+    }
+
+    #[test]
+    fn downloads_typed_rows() {
+        let mut client = client(&stand_in());
+
+        assert_eq!(
+            client.list_tables().unwrap(),
+            vec!["users", "enrollments", "courses"]
+        );
+
+        let users: Vec<Record<User>> = client
+            .rows(&Query::Snapshot)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].key.id, 1);
+        assert_eq!(
+            users[1].value.as_ref().unwrap().name.as_deref(),
+            Some("Alan")
+        );
+    }
+
+    #[test]
+    fn stops_waiting_for_jobs() {
+        let mut client = client(&stand_in()).job_timeout(Duration::from_millis(50));
+
+        match client.rows::<Enrollment>(&Query::Snapshot) {
+            Err(DataError::JobTimedOut(id, _)) => assert_eq!(id, "job-3"),
+            _ => panic!("The job should time out"),
+        }
+    }
+
+    #[test]
+    fn reports_failed_jobs() {
+        let mut client = client(&stand_in());
+
+        match client.rows::<Course>(&Query::Snapshot) {
+            Err(DataError::JobFailed(id, message)) => {
+                assert_eq!(id, "job-2");
+                assert_eq!(message, "Boom");
+            }
+            _ => panic!("The job should fail"),
+        }
+    }
+
+    #[test]
+    fn reads_deleted_rows_from_files() {
+        let path = env::temp_dir().join(format!("canvas-data-test-{}.json.gz", std::process::id()));
+        fs::write(
+            &path,
+            gzip(
+                r#"{"key":{"id":3},"meta":{"action":"D","ts":"2021-03-02T10:00:00Z"}}
+"#,
+            ),
+        )
+        .unwrap();
+
+        let users: Vec<Record<User>> = RowIterator::from_files(&[&path])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(users.len(), 1);
+        assert!(users[0].is_deleted());
+    }
+
+    #[test]
+    fn sends_since_only_in_incremental_queries() {
+        let since = "2021-03-01T00:00:00Z".parse().unwrap();
+        let snapshot = QueryBody {
+            format: "jsonl",
+            since: None,
+        };
+        let incremental = QueryBody {
+            format: "jsonl",
+            since: Some(since),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&snapshot).unwrap(),
+            json!({"format": "jsonl"})
+        );
+        assert_eq!(
+            serde_json::to_value(&incremental).unwrap(),
+            json!({"format": "jsonl", "since": "2021-03-01T00:00:00Z"})
+        );
+    }
+}
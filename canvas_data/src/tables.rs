@@ -0,0 +1,114 @@
+//! Typed rows of the Canvas Data tables
+//!
+//! Every line of a downloaded file is a [`Record`]: the primary key of the
+//! row, its columns (`value`) and some metadata. In incremental files, rows
+//! that were deleted have no value.
+//!
+//! Only some columns of every table are read. Others are ignored.
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// A table that can be downloaded with typed rows
+pub trait Table: DeserializeOwned {
+    /// Name of the table in Canvas Data
+    const NAME: &'static str;
+}
+
+/// A line in a downloaded file
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Record<T> {
+    pub key: Key,
+
+    /// The columns of the row. `None` if the row was deleted
+    pub value: Option<T>,
+    pub meta: Meta,
+}
+
+/// Primary key of a row
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub id: u64,
+}
+
+/// Metadata of a row
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Meta {
+    /// `U` if the row was inserted or updated, `D` if it was deleted
+    pub action: Option<String>,
+
+    /// Time of the change
+    pub ts: Option<DateTime<Utc>>,
+}
+
+/// Row of the `users` table
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct User {
+    pub name: Option<String>,
+    pub sortable_name: Option<String>,
+    pub workflow_state: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Row of the `enrollments` table
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Enrollment {
+    pub user_id: u64,
+    pub course_id: u64,
+    pub course_section_id: Option<u64>,
+    pub role_id: Option<u64>,
+
+    /// `StudentEnrollment`, `TeacherEnrollment` and so on
+    #[serde(rename = "type")]
+    pub enrollment_type: String,
+    pub workflow_state: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Row of the `courses` table
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Course {
+    pub name: String,
+    pub course_code: Option<String>,
+    pub sis_source_id: Option<String>,
+    pub account_id: u64,
+    pub enrollment_term_id: Option<u64>,
+    pub workflow_state: String,
+}
+
+/// Row of the `course_sections` table
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CourseSection {
+    pub name: String,
+    pub course_id: u64,
+    pub sis_source_id: Option<String>,
+
+    /// If the section is cross-listed, the course where it was originally
+    pub nonxlist_course_id: Option<u64>,
+    pub workflow_state: String,
+}
+
+impl<T> Record<T> {
+    /// Returns `true` if the row was deleted
+    pub fn is_deleted(&self) -> bool {
+        self.value.is_none() || self.meta.action.as_deref() == Some("D")
+    }
+}
+
+impl Table for User {
+    const NAME: &'static str = "users";
+}
+
+impl Table for Enrollment {
+    const NAME: &'static str = "enrollments";
+}
+
+impl Table for Course {
+    const NAME: &'static str = "courses";
+}
+
+impl Table for CourseSection {
+    const NAME: &'static str = "course_sections";
+}
@@ -1,9 +1,9 @@
 mod kaltura_api;
 
-use csv::Writer;
 use dialoguer::{theme::ColorfulTheme, Input};
 use kaltura_api::KalturaCategory;
 use serde::Serialize;
+use table_output::TableWriter;
 
 /// Prompt the "ks"
 fn prompt_ks() -> String {
@@ -13,7 +13,8 @@ fn prompt_ks() -> String {
         .expect("Failed to prompt file name")
 }
 
-/// Prompt a file-name
+/// Prompt a file-name. The extension (`.csv`, `.json`, `.ndjson` or `.xlsx`)
+/// chooses the format of the file
 fn prompt_filename() -> String {
     Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Write a filename")
@@ -22,7 +23,7 @@ fn prompt_filename() -> String {
 }
 
 fn write_categories(categories: impl Iterator<Item = KalturaCategory>, filename: &str) {
-    let mut wtr = Writer::from_path(filename).expect("Error when creating the writer");
+    let mut wtr = TableWriter::create(filename, None).expect("Error when creating the writer");
 
     #[derive(Serialize)]
     struct Row {
@@ -31,13 +32,13 @@ fn write_categories(categories: impl Iterator<Item = KalturaCategory>, filename:
     }
 
     for category in categories {
-        wtr.serialize(Row {
+        wtr.serialize(&Row {
             course_code: category.name,
             count: category.entries_count,
         })
         .expect("Error when writing a row");
     }
-    wtr.flush().expect("Error when flushing the writer");
+    wtr.finish().expect("Error when finishing the file");
 }
 
 fn main() {
@@ -0,0 +1,195 @@
+//! Client for the Kopps API (<https://api.kth.se/api/kopps/v2>), where KTH
+//! publishes its courses and course rounds.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use kth_terms::{Semester, Term};
+//! use kopps_api::{KoppsApi, OfferingsQuery};
+//!
+//! let kopps = KoppsApi::from_env().unwrap();
+//! let query = OfferingsQuery::semester(Semester::new(2021, Term::Spring));
+//!
+//! for round in kopps.course_rounds(&query).unwrap() {
+//!     println!("{}", round.sis_id());
+//! }
+//! ```
+pub mod models;
+pub mod offerings;
+pub mod periods;
+
+pub use api_client::ApiError;
+pub use kth_terms::{Semester, Term};
+pub use models::{Course, CourseRound, Department, OfferedSemester, School};
+pub use offerings::{OfferingsQuery, PeriodMatch};
+pub use periods::Period;
+
+use api_client::ApiClient;
+use std::collections::HashSet;
+use std::env;
+
+/// URL used by [`KoppsApi::from_env`] when `KOPPS_API_URL` is not set
+pub const DEFAULT_URL: &str = "https://api.kth.se/api/kopps/v2";
+
+/// Client for the Kopps API
+#[derive(Clone, Debug)]
+pub struct KoppsApi {
+    client: ApiClient,
+}
+
+impl KoppsApi {
+    /// Creates a client for the Kopps API in `url`, with the default
+    /// timeout and retries of [`ApiClient`]
+    pub fn new(url: &str) -> Result<KoppsApi, ApiError> {
+        Ok(KoppsApi::with_client(ApiClient::new(url)?))
+    }
+
+    /// Creates a client for the URL in the `KOPPS_API_URL` environmental
+    /// variable, or [`DEFAULT_URL`] if it is not set
+    pub fn from_env() -> Result<KoppsApi, ApiError> {
+        let url = env::var("KOPPS_API_URL").unwrap_or_else(|_| DEFAULT_URL.to_string());
+        KoppsApi::new(&url)
+    }
+
+    /// Creates a client that performs the requests with `client`. Use it to
+    /// set other timeouts or retries
+    pub fn with_client(client: ApiClient) -> KoppsApi {
+        KoppsApi { client }
+    }
+
+    /// Get a course by its code, like `SF1624`
+    pub fn course(&self, code: &str) -> Result<Course, ApiError> {
+        self.client.get_json(&format!("course/{}", code), &[])
+    }
+
+    /// Get all the schools
+    pub fn schools(&self) -> Result<Vec<School>, ApiError> {
+        self.client.get_json("schools", &[])
+    }
+
+    /// Get all the departments
+    pub fn departments(&self) -> Result<Vec<Department>, ApiError> {
+        self.client.get_json("departments", &[])
+    }
+
+    /// Get the course rounds that match a query.
+    ///
+    /// The semester, school, department and state are sent to Kopps, and
+    /// checked again in the response. With [`PeriodMatch::Starts`], the
+    /// periods are sent too, since Kopps knows the first period of every
+    /// round. With [`PeriodMatch::RunsIn`], the periods are checked here with
+    /// the dates of the round, and the previous semester is requested too,
+    /// since rounds that run in a period can start in it.
+    ///
+    /// Every round is returned once, even if it matches several periods.
+    pub fn course_rounds(&self, query: &OfferingsQuery) -> Result<Vec<CourseRound>, ApiError> {
+        let from = query.first_semester().kopps_code();
+        let to = query.semester.kopps_code();
+        let periods: Vec<String> = match query.period_match {
+            PeriodMatch::Starts => query.periods.iter().map(|p| p.to_string()).collect(),
+            PeriodMatch::RunsIn => vec![],
+        };
+        let mut params = vec![
+            ("from", from.as_str()),
+            ("to", to.as_str()),
+            ("skip_coordinator_info", "true"),
+        ];
+
+        if let Some(school) = &query.school {
+            params.push(("school", school));
+        }
+
+        if let Some(department) = &query.department {
+            params.push(("department", department));
+        }
+
+        if let Some(state) = &query.state {
+            params.push(("state", state));
+        }
+
+        for period in &periods {
+            params.push(("period", period));
+        }
+
+        let rounds: Vec<CourseRound> = self.client.get_json("courses/offerings", &params)?;
+        let mut seen = HashSet::new();
+
+        Ok(rounds
+            .into_iter()
+            .filter(|round| round.matches(query) && seen.insert(round.sis_id()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kth_terms::{Semester, Term};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Starts a server that responds `body` once. Returns its URL and the
+    /// request line it received
+    fn server(body: String) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/kopps/v2", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            sender.send(request_line).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        (url, receiver)
+    }
+
+    #[test]
+    fn requests_course_rounds() {
+        let round = r#"{
+            "course_code": "SF1624", "first_semester": "20211",
+            "first_period": "20211P3", "school_code": "SCI",
+            "department_code": "SF", "state": "APPROVED", "offering_id": "1",
+            "offered_semesters": [{
+                "start_date": "2021-01-18", "end_date": "2021-03-19",
+                "start_week": "3", "end_week": "11", "semester": "20211"
+            }]
+        }"#;
+        let (url, requests) = server(format!("[{0}, {0}]", round));
+        let kopps = KoppsApi::new(&url).unwrap();
+
+        let query = OfferingsQuery {
+            school: Some("SCI".to_string()),
+            periods: vec!["20211P3".parse().unwrap()],
+            ..OfferingsQuery::semester(Semester::new(2021, Term::Spring))
+        };
+        let rounds = kopps.course_rounds(&query).unwrap();
+
+        assert_eq!(rounds.len(), 1);
+        assert_eq!(rounds[0].sis_id(), "SF1624202111");
+        assert_eq!(
+            requests.recv().unwrap().trim(),
+            "GET /api/kopps/v2/courses/offerings?from=20211&to=20211&skip_coordinator_info=true&school=SCI&period=20211P3 HTTP/1.1"
+        );
+    }
+}
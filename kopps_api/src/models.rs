@@ -0,0 +1,75 @@
+//! Data returned by Kopps
+use serde::Deserialize;
+
+/// A course, like SF1624
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Course {
+    pub code: String,
+    pub title: Option<String>,
+
+    /// Title in the other language (English if `title` is in Swedish)
+    pub title_other: Option<String>,
+    pub credits: Option<f64>,
+    pub department: Option<Department>,
+
+    /// `ESTABLISHED`, `DEACTIVATED`...
+    pub state: Option<String>,
+}
+
+/// A school, like EECS
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct School {
+    pub code: String,
+    pub name: String,
+}
+
+/// A department, like "JH"
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Department {
+    pub code: String,
+    pub name: String,
+    pub school_code: Option<String>,
+}
+
+/// A course round (an offering of a course), as returned by the
+/// `courses/offerings` endpoint
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CourseRound {
+    pub course_code: String,
+    pub first_semester: String,
+
+    /// Period where the round starts, like `20201P3`
+    pub first_period: String,
+    pub school_code: String,
+    pub department_code: Option<String>,
+
+    /// `APPROVED`, `FULL`, `CANCELLED`...
+    pub state: String,
+    pub offering_id: String,
+
+    /// Every semester where the round has teaching
+    pub offered_semesters: Vec<OfferedSemester>,
+}
+
+/// Part of a course round in one semester
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OfferedSemester {
+    /// `YYYY-MM-DD`
+    pub start_date: String,
+    pub end_date: String,
+    pub start_week: String,
+    pub end_week: String,
+
+    /// Kopps code of the semester, like `20201`
+    pub semester: String,
+}
+
+impl CourseRound {
+    /// Returns the SIS ID of the course room of the round in Canvas
+    pub fn sis_id(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.course_code, self.first_semester, self.offering_id
+        )
+    }
+}
@@ -0,0 +1,249 @@
+//! Queries for course offerings
+use crate::models::CourseRound;
+use crate::periods::Period;
+use chrono::NaiveDate;
+use kth_terms::{Semester, Term};
+
+/// How a round must relate to the periods in [`OfferingsQuery`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodMatch {
+    /// The round starts in one of the periods
+    Starts,
+
+    /// The round runs during one of the periods, even if it started before
+    RunsIn,
+}
+
+/// Filters for the course offerings. Fields set to `None` do not filter.
+#[derive(Debug, Clone)]
+pub struct OfferingsQuery {
+    pub semester: Semester,
+
+    /// Periods of the semester. If empty, all of them
+    pub periods: Vec<Period>,
+    pub period_match: PeriodMatch,
+
+    /// School code, like `EECS`
+    pub school: Option<String>,
+
+    /// Department code, like `JH`
+    pub department: Option<String>,
+
+    /// State of the round, like `APPROVED` or `FULL`
+    pub state: Option<String>,
+}
+
+impl OfferingsQuery {
+    /// Rounds that start in a semester
+    pub fn semester(semester: Semester) -> OfferingsQuery {
+        OfferingsQuery {
+            semester,
+            periods: vec![],
+            period_match: PeriodMatch::Starts,
+            school: None,
+            department: None,
+            state: None,
+        }
+    }
+
+    /// Returns the periods in the query, or all the periods of the semester
+    /// if there are none
+    pub fn selected_periods(&self) -> Vec<Period> {
+        match self.periods.is_empty() {
+            true => Period::all_in(self.semester),
+            false => self.periods.clone(),
+        }
+    }
+
+    /// Returns the first semester to request. Rounds that run in a period
+    /// can start in the previous semester
+    pub(crate) fn first_semester(&self) -> Semester {
+        match self.period_match {
+            PeriodMatch::Starts => self.semester,
+            PeriodMatch::RunsIn => match self.semester.term {
+                Term::Spring => Semester::new(self.semester.year - 1, Term::Autumn),
+                Term::Autumn => Semester::new(self.semester.year, Term::Spring),
+            },
+        }
+    }
+}
+
+impl CourseRound {
+    /// Returns the periods where the round has teaching, in all its
+    /// semesters
+    pub fn periods(&self) -> Vec<Period> {
+        let mut periods = vec![];
+
+        for offered in &self.offered_semesters {
+            let semester = match Semester::from_kopps(&offered.semester) {
+                Some(semester) => semester,
+                None => continue,
+            };
+            let (start, end) = match (
+                parse_date(&offered.start_date),
+                parse_date(&offered.end_date),
+            ) {
+                (Some(start), Some(end)) => (start, end),
+                _ => continue,
+            };
+
+            periods.extend(
+                Period::all_in(semester)
+                    .into_iter()
+                    .filter(|p| p.overlaps(start, end)),
+            );
+        }
+
+        // Rounds without dates at least run in their first period
+        if periods.is_empty() {
+            periods.extend(self.first_period.parse::<Period>().ok());
+        }
+
+        periods
+    }
+
+    /// Returns the periods of the query where the round runs, according to
+    /// its [`periods`](CourseRound::periods). With [`PeriodMatch::Starts`],
+    /// the list is empty unless the first period of the round is in the
+    /// query
+    pub fn matching_periods(&self, query: &OfferingsQuery) -> Vec<Period> {
+        let selected = query.selected_periods();
+        let first_period = self.first_period.parse::<Period>().ok();
+
+        if query.period_match == PeriodMatch::Starts
+            && !first_period.is_some_and(|p| selected.contains(&p))
+        {
+            return vec![];
+        }
+
+        let round_periods = self.periods();
+
+        selected
+            .into_iter()
+            .filter(|p| round_periods.contains(p) || Some(*p) == first_period)
+            .collect()
+    }
+
+    /// Returns `true` if the round is selected by the query
+    pub fn matches(&self, query: &OfferingsQuery) -> bool {
+        !self.matching_periods(query).is_empty()
+            && matches_filter(&query.school, Some(&self.school_code))
+            && matches_filter(&query.department, self.department_code.as_ref())
+            && matches_filter(&query.state, Some(&self.state))
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Compares codes ignoring case. A filter set to `None` matches everything
+fn matches_filter(filter: &Option<String>, value: Option<&String>) -> bool {
+    match (filter, value) {
+        (None, _) => true,
+        (Some(filter), Some(value)) => filter.eq_ignore_ascii_case(value),
+        (Some(_), None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OfferedSemester;
+
+    fn round(first_period: &str, semesters: &[(&str, &str, &str)]) -> CourseRound {
+        CourseRound {
+            course_code: "SF1624".to_string(),
+            first_semester: first_period[..5].to_string(),
+            first_period: first_period.to_string(),
+            school_code: "SCI".to_string(),
+            department_code: Some("SF".to_string()),
+            state: "APPROVED".to_string(),
+            offering_id: "1".to_string(),
+            offered_semesters: semesters
+                .iter()
+                .map(|(semester, start, end)| OfferedSemester {
+                    start_date: start.to_string(),
+                    end_date: end.to_string(),
+                    start_week: String::new(),
+                    end_week: String::new(),
+                    semester: semester.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn finds_all_periods_of_a_round() {
+        let round = round(
+            "20202P2",
+            &[
+                ("20202", "2020-11-02", "2021-01-15"),
+                ("20211", "2021-01-18", "2021-06-04"),
+            ],
+        );
+        let periods: Vec<String> = round.periods().iter().map(|p| p.to_string()).collect();
+
+        assert_eq!(periods, vec!["20202P2", "20211P3", "20211P4"]);
+        assert_eq!(round.sis_id(), "SF1624202021");
+
+        let p4: Period = "20211P4".parse().unwrap();
+        let starting_in_p4 = OfferingsQuery {
+            periods: vec![p4],
+            ..OfferingsQuery::semester(p4.semester())
+        };
+        let running_in_p4 = OfferingsQuery {
+            period_match: PeriodMatch::RunsIn,
+            ..starting_in_p4.clone()
+        };
+        assert!(round.matches(&running_in_p4));
+        assert!(!round.matches(&starting_in_p4));
+        assert!(!round.matches(&OfferingsQuery {
+            school: Some("EECS".to_string()),
+            ..running_in_p4
+        }));
+
+        let whole_spring = OfferingsQuery {
+            period_match: PeriodMatch::RunsIn,
+            ..OfferingsQuery::semester(p4.semester())
+        };
+        let periods: Vec<String> = round
+            .matching_periods(&whole_spring)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(periods, vec!["20211P3", "20211P4"]);
+        assert_eq!(
+            whole_spring.first_semester(),
+            Semester::new(2020, Term::Autumn)
+        );
+        assert!(round.matches(&OfferingsQuery::semester(Semester::new(2020, Term::Autumn))));
+    }
+
+    #[test]
+    fn finds_the_periods_of_rounds_that_start_in_the_query() {
+        let round = round("20211P3", &[("20211", "2021-01-18", "2021-06-04")]);
+        let names = |query: &OfferingsQuery| -> Vec<String> {
+            round
+                .matching_periods(query)
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        };
+        let spring = OfferingsQuery::semester(Semester::new(2021, Term::Spring));
+
+        assert_eq!(names(&spring), vec!["20211P3", "20211P4"]);
+        assert_eq!(
+            names(&OfferingsQuery {
+                periods: vec!["20211P3".parse().unwrap()],
+                ..spring.clone()
+            }),
+            vec!["20211P3"]
+        );
+        assert!(names(&OfferingsQuery {
+            periods: vec!["20211P4".parse().unwrap()],
+            ..spring
+        })
+        .is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Study periods
+use chrono::{NaiveDate, Weekday};
+use kth_terms::{Semester, Term};
+use std::fmt;
+use std::str::FromStr;
+
+/// A study period like `20201P3`: the third period, in the spring of 2020.
+///
+/// Spring has periods 3, 4 and 5 (summer). Autumn has periods 0 (summer), 1
+/// and 2. Other combinations cannot be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Period {
+    semester: Semester,
+    number: u8,
+}
+
+impl Period {
+    /// Returns the period, or an error if the semester does not have it
+    pub fn new(semester: Semester, number: u8) -> Result<Period, String> {
+        match numbers(semester.term).contains(&number) {
+            true => Ok(Period { semester, number }),
+            false => Err(format!("{} does not have a period {}", semester, number)),
+        }
+    }
+
+    pub fn semester(&self) -> Semester {
+        self.semester
+    }
+
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the periods of a semester
+    pub fn all_in(semester: Semester) -> Vec<Period> {
+        numbers(semester.term)
+            .iter()
+            .map(|&number| Period { semester, number })
+            .collect()
+    }
+
+    /// Returns the approximate first and last day of the period, from the
+    /// usual ISO weeks of the KTH study periods. They are only used to find
+    /// the periods where a round runs (see
+    /// [`PeriodMatch::RunsIn`](crate::PeriodMatch::RunsIn)). Rounds that
+    /// start in a period are found by Kopps.
+    pub fn dates(&self) -> (NaiveDate, NaiveDate) {
+        let year = self.semester.year;
+        let (first_week, last_week) = match self.number {
+            3 => ((year, 3), (year, 12)),
+            4 => ((year, 13), (year, 23)),
+            0 | 5 => ((year, 24), (year, 34)),
+            1 => ((year, 35), (year, 44)),
+            2 => ((year, 45), (year + 1, 2)),
+            number => unreachable!("Period {} is rejected by Period::new", number),
+        };
+
+        (
+            NaiveDate::from_isoywd(first_week.0, first_week.1, Weekday::Mon),
+            NaiveDate::from_isoywd(last_week.0, last_week.1, Weekday::Sun),
+        )
+    }
+
+    /// Returns `true` if the period has days between `start` and `end`
+    pub fn overlaps(&self, start: NaiveDate, end: NaiveDate) -> bool {
+        let (first, last) = self.dates();
+        start <= last && first <= end
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    /// Reads a period like `20201P3`
+    fn from_str(s: &str) -> Result<Period, String> {
+        let error = || format!("`{}` is not a period like 20201P3", s);
+        let (semester, number) = s.split_at(s.find('P').ok_or_else(error)?);
+        let semester = Semester::from_kopps(semester).ok_or_else(error)?;
+        let number = number[1..].parse().map_err(|_| error())?;
+
+        Period::new(semester, number)
+    }
+}
+
+/// Numbers of the periods of a term
+fn numbers(term: Term) -> &'static [u8] {
+    match term {
+        Term::Spring => &[3, 4, 5],
+        Term::Autumn => &[0, 1, 2],
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}P{}", self.semester.kopps_code(), self.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_periods() {
+        let period: Period = "20201P3".parse().unwrap();
+
+        assert_eq!(
+            period,
+            Period::new(Semester::new(2020, Term::Spring), 3).unwrap()
+        );
+        assert!(Period::new(Semester::new(2020, Term::Autumn), 7).is_err());
+        assert_eq!(period.to_string(), "20201P3");
+        assert!("20201P1".parse::<Period>().is_err());
+        assert!("20201".parse::<Period>().is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! KTH semesters, shared by the clients of Canvas, Kopps and other KTH APIs
+//!
+//! Kopps and Ladok write semesters in two ways: `20201` (Kopps, year and
+//! `1` for spring or `2` for autumn) and `VT2020` (Ladok). [`Semester`]
+//! understands both.
+//!
+//! Example:
+//!
+//! ```
+//! use kth_terms::{Semester, Term};
+//!
+//! let semester: Semester = "VT2020".parse().unwrap();
+//!
+//! assert_eq!(semester, Semester::new(2020, Term::Spring));
+//! assert_eq!(semester.kopps_code(), "20201");
+//! ```
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+/// Half of an academic year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term {
+    /// Spring term, "VT" (vårtermin)
+    Spring,
+
+    /// Autumn term, "HT" (hösttermin)
+    Autumn,
+}
+
+/// A semester like "VT2020" or "20201"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Semester {
+    pub year: i32,
+    pub term: Term,
+}
+
+impl Semester {
+    pub fn new(year: i32, term: Term) -> Semester {
+        Semester { year, term }
+    }
+
+    /// Reads a Kopps code like `20201`
+    pub fn from_kopps(code: &str) -> Option<Semester> {
+        if code.len() != 5 || !code.is_char_boundary(4) {
+            return None;
+        }
+
+        let year = code[..4].parse().ok()?;
+        let term = match &code[4..] {
+            "1" => Term::Spring,
+            "2" => Term::Autumn,
+            _ => return None,
+        };
+
+        Some(Semester { year, term })
+    }
+
+    /// Reads a Ladok code like `VT2020`
+    pub fn from_ladok(code: &str) -> Option<Semester> {
+        if code.len() != 6 || !code.is_char_boundary(2) {
+            return None;
+        }
+
+        let term = match &code[..2].to_uppercase()[..] {
+            "VT" => Term::Spring,
+            "HT" => Term::Autumn,
+            _ => return None,
+        };
+        let year = code[2..].parse().ok()?;
+
+        Some(Semester { year, term })
+    }
+
+    /// Returns the semester that a date belongs to. Autumn terms end in
+    /// mid-January, so early January belongs to the autumn of the previous
+    /// year.
+    pub fn containing(date: NaiveDate) -> Semester {
+        match (date.month(), date.day()) {
+            (1, day) if day < 15 => Semester::new(date.year() - 1, Term::Autumn),
+            (month, _) if month < 8 => Semester::new(date.year(), Term::Spring),
+            _ => Semester::new(date.year(), Term::Autumn),
+        }
+    }
+
+    /// Returns the Kopps code, like `20201`
+    pub fn kopps_code(&self) -> String {
+        let term = match self.term {
+            Term::Spring => 1,
+            Term::Autumn => 2,
+        };
+
+        format!("{}{}", self.year, term)
+    }
+
+    /// Returns the Ladok code, like `VT2020`
+    pub fn ladok_code(&self) -> String {
+        let term = match self.term {
+            Term::Spring => "VT",
+            Term::Autumn => "HT",
+        };
+
+        format!("{}{}", term, self.year)
+    }
+
+    /// Returns a date in the middle of the semester
+    pub fn middle(&self) -> NaiveDate {
+        match self.term {
+            Term::Spring => NaiveDate::from_ymd(self.year, 4, 1),
+            Term::Autumn => NaiveDate::from_ymd(self.year, 10, 15),
+        }
+    }
+}
+
+impl FromStr for Semester {
+    type Err = String;
+
+    /// Reads either a Kopps code (`20201`) or a Ladok code (`VT2020`)
+    fn from_str(s: &str) -> Result<Semester, String> {
+        Semester::from_kopps(s)
+            .or_else(|| Semester::from_ladok(s))
+            .ok_or_else(|| format!("`{}` is not a semester like 20201 or VT2020", s))
+    }
+}
+
+impl fmt::Display for Semester {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.ladok_code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_semesters() {
+        let vt2020 = Semester::new(2020, Term::Spring);
+
+        assert_eq!("20201".parse(), Ok(vt2020));
+        assert_eq!("VT2020".parse(), Ok(vt2020));
+        assert_eq!("ht2020".parse(), Ok(Semester::new(2020, Term::Autumn)));
+        assert!("20203".parse::<Semester>().is_err());
+        assert_eq!(vt2020.kopps_code(), "20201");
+        assert_eq!(vt2020.ladok_code(), "VT2020");
+        assert_eq!(
+            Semester::containing(NaiveDate::from_ymd(2021, 1, 10)),
+            Semester::new(2020, Term::Autumn)
+        );
+    }
+}
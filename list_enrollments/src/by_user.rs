@@ -0,0 +1,168 @@
+use crate::columns::{Column, Row};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// All the enrollments of a user, in one row
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UserRow {
+    name: String,
+    mail1: String,
+    mail2: String,
+
+    /// Course rooms and exam rooms, separated by `; `. The values of the
+    /// lists may contain spaces
+    courses: String,
+    sections: String,
+    roles: String,
+    enrollments: usize,
+}
+
+/// Separator of the values in the list columns of [`UserRow`]
+const SEPARATOR: &str = "; ";
+
+/// Enrollments aggregated by user
+#[derive(Default)]
+pub struct UserSummaries {
+    users: HashMap<u64, Summary>,
+}
+
+struct Summary {
+    name: String,
+    mail1: String,
+    mail2: String,
+    courses: Vec<String>,
+    sections: Vec<String>,
+    roles: Vec<String>,
+    enrollments: usize,
+}
+
+impl UserSummaries {
+    /// Names of the fields of [`UserRow`]
+    pub const HEADERS: &'static [&'static str] = &[
+        "name",
+        "mail1",
+        "mail2",
+        "courses",
+        "sections",
+        "roles",
+        "enrollments",
+    ];
+
+    pub fn add(&mut self, row: &Row) {
+        let summary = self
+            .users
+            .entry(row.enrollment.user_id)
+            .or_insert_with(|| Summary {
+                name: row.value(Column::Name),
+                mail1: row.value(Column::Mail1),
+                mail2: row.value(Column::Mail2),
+                courses: vec![],
+                sections: vec![],
+                roles: vec![],
+                enrollments: 0,
+            });
+
+        push_unique(&mut summary.courses, row.course.to_string());
+        push_unique(&mut summary.sections, row.value(Column::Section));
+        push_unique(&mut summary.roles, row.value(Column::Role));
+        summary.enrollments += 1;
+    }
+
+    /// Returns one row per user, sorted by name
+    pub fn rows(self) -> Vec<UserRow> {
+        let mut rows: Vec<UserRow> = self
+            .users
+            .into_values()
+            .map(|s| UserRow {
+                name: s.name,
+                mail1: s.mail1,
+                mail2: s.mail2,
+                courses: s.courses.join(SEPARATOR),
+                sections: s.sections.join(SEPARATOR),
+                roles: s.roles.join(SEPARATOR),
+                enrollments: s.enrollments,
+            })
+            .collect();
+
+        rows.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.mail1.cmp(&b.mail1)));
+        rows
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas_api::{self, Enrollment, User};
+    use crate::mail::MailTemplate;
+
+    fn enrollment(user_id: u64, name: &str, section: &str, role: &str) -> Enrollment {
+        Enrollment {
+            sis_user_id: Some(format!("u{}", user_id)),
+            sis_section_id: Some(section.to_string()),
+            role: role.to_string(),
+            user: User {
+                sortable_name: Some(name.to_string()),
+                login_id: None,
+            },
+            ..canvas_api::enrollment(user_id)
+        }
+    }
+
+    #[test]
+    fn aggregates_by_user() {
+        let template = MailTemplate::default();
+        let mut users = UserSummaries::default();
+
+        for (enrollment, course) in &[
+            (
+                enrollment(2, "B", "SF1624VT211", "StudentEnrollment"),
+                "SF1624VT211",
+            ),
+            (
+                enrollment(1, "A", "SF1624VT211", "TeacherEnrollment"),
+                "SF1624VT211",
+            ),
+            (
+                enrollment(1, "A", "DD1337HT201", "TeacherEnrollment"),
+                "DD1337HT201",
+            ),
+            (
+                enrollment(1, "A", "DD1337HT201", "TaEnrollment"),
+                "DD1337HT201",
+            ),
+        ] {
+            users.add(&Row {
+                enrollment,
+                course,
+                role_label: "",
+                canvas_email: None,
+                mail_template: &template,
+                period: None,
+                activity: None,
+                section_type: None,
+            });
+        }
+
+        let rows = users.rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            UserRow {
+                name: "A".to_string(),
+                mail1: "u1@kth.se".to_string(),
+                mail2: "??".to_string(),
+                courses: "SF1624VT211; DD1337HT201".to_string(),
+                sections: "SF1624VT211; DD1337HT201".to_string(),
+                roles: "TeacherEnrollment; TaEnrollment".to_string(),
+                enrollments: 3,
+            }
+        );
+        assert_eq!(rows[1].enrollments, 1);
+    }
+}
@@ -1,32 +1,121 @@
 extern crate canvas_api;
 
 use canvas_api::CanvasApi;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct Enrollment {
     pub id: i32,
+    pub user_id: u64,
+    pub course_id: u64,
     pub sis_user_id: Option<String>,
     pub sis_section_id: Option<String>,
     pub role: String,
+
+    /// Base role, like `TeacherEnrollment` for the custom role "Course
+    /// Responsible"
+    #[serde(rename = "type")]
+    pub enrollment_type: String,
+    pub enrollment_state: EnrollmentState,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+
+    /// Last time the user did something in the course
+    pub last_activity_at: Option<DateTime<Utc>>,
     pub user: User,
 }
 
+/// Active student enrollment of a user in the course 1, without optional
+/// values. Tests change the fields they need with `..enrollment(user_id)`
+#[cfg(test)]
+pub fn enrollment(user_id: u64) -> Enrollment {
+    Enrollment {
+        id: 1,
+        user_id,
+        course_id: 1,
+        sis_user_id: None,
+        sis_section_id: None,
+        role: "StudentEnrollment".to_string(),
+        enrollment_type: "StudentEnrollment".to_string(),
+        enrollment_state: EnrollmentState::Active,
+        created_at: None,
+        updated_at: None,
+        last_activity_at: None,
+        user: User {
+            sortable_name: None,
+            login_id: None,
+        },
+    }
+}
+
+/// State of an enrollment in Canvas
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum EnrollmentState {
+    Active,
+
+    /// The user has not accepted the invitation yet
+    Invited,
+    CreationPending,
+    Deleted,
+    Rejected,
+
+    /// Concluded
+    Completed,
+
+    /// Deactivated by a teacher or an admin
+    Inactive,
+}
+
+impl EnrollmentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnrollmentState::Active => "active",
+            EnrollmentState::Invited => "invited",
+            EnrollmentState::CreationPending => "creation_pending",
+            EnrollmentState::Deleted => "deleted",
+            EnrollmentState::Rejected => "rejected",
+            EnrollmentState::Completed => "completed",
+            EnrollmentState::Inactive => "inactive",
+        }
+    }
+
+    /// Returns the value of `enrollment_state[]` in the list of users of a
+    /// course. That endpoint does not filter by the states `creation_pending`
+    /// and `deleted`
+    fn user_filter(&self) -> Option<&'static str> {
+        match self {
+            EnrollmentState::CreationPending | EnrollmentState::Deleted => None,
+            state => Some(state.as_str()),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct User {
     pub sortable_name: Option<String>,
     pub login_id: Option<String>,
 }
 
+/// Get the enrollments of a section in some states. If `states` is empty,
+/// Canvas returns the active and invited ones
 pub fn get_enrollments(
-    canvas_api_url: &str,
-    canvas_api_token: &str,
+    canvas_api: &CanvasApi,
     sis_section_id: &str,
+    states: &[EnrollmentState],
 ) -> Result<Vec<Enrollment>, Box<dyn std::error::Error>> {
-    let canvas_api = CanvasApi::new(canvas_api_url, canvas_api_token);
+    let query: Vec<String> = states
+        .iter()
+        .map(|state| format!("state[]={}", state.as_str()))
+        .collect();
     let pages = canvas_api.get_paginated(&format!(
-        "/sections/sis_section_id:{}/enrollments",
-        sis_section_id
+        "/sections/sis_section_id:{}/enrollments?{}",
+        sis_section_id,
+        query.join("&")
     ));
 
     let mut all_enrollments: Vec<Enrollment> = vec![];
@@ -52,3 +141,72 @@ pub fn get_enrollments(
 
     Ok(all_enrollments)
 }
+
+#[derive(Deserialize, Debug)]
+struct UserWithEmail {
+    id: u64,
+    email: Option<String>,
+}
+
+/// Get the primary e-mail (as registered in Canvas) of the users in a course
+/// with enrollments in some states, by user ID. If `states` is empty, Canvas
+/// returns the users with active and invited enrollments. The states
+/// `creation_pending` and `deleted` are ignored, since Canvas cannot list
+/// users by them. Users without e-mail are not included
+pub fn get_emails(
+    canvas_api: &CanvasApi,
+    course_id: u64,
+    states: &[EnrollmentState],
+) -> Result<HashMap<u64, String>, Box<dyn std::error::Error>> {
+    let query = users_query(states);
+
+    if query.is_empty() && !states.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let pages = canvas_api.get_paginated(&format!(
+        "/courses/{}/users?include[]=email&per_page=100{}",
+        course_id, query
+    ));
+
+    let mut emails = HashMap::new();
+
+    for response in pages {
+        let users = response?.error_for_status()?.json::<Vec<UserWithEmail>>()?;
+
+        for user in users {
+            if let Some(email) = user.email {
+                emails.insert(user.id, email);
+            }
+        }
+    }
+
+    Ok(emails)
+}
+
+/// Returns the `enrollment_state[]` parameters of the list of users of a
+/// course, without the states that it does not accept
+fn users_query(states: &[EnrollmentState]) -> String {
+    states
+        .iter()
+        .filter_map(EnrollmentState::user_filter)
+        .map(|state| format!("&enrollment_state[]={}", state))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_users_by_supported_states() {
+        assert_eq!(
+            users_query(&[EnrollmentState::Completed, EnrollmentState::Deleted]),
+            "&enrollment_state[]=completed"
+        );
+        assert_eq!(
+            users_query(&[EnrollmentState::CreationPending, EnrollmentState::Deleted]),
+            ""
+        );
+    }
+}
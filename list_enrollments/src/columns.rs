@@ -0,0 +1,311 @@
+use crate::canvas_api::Enrollment;
+use crate::mail::MailTemplate;
+use akt_api::Aktivitetstillfalle;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A column of the output file
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "snake_case")]
+pub enum Column {
+    /// Name of the user, as "Surname, Name"
+    Name,
+
+    /// ID of the user in Canvas
+    UserId,
+
+    /// SIS ID of the course room or exam room
+    Course,
+
+    /// Name of the role in Canvas, like StudentEnrollment or "Course
+    /// Responsible"
+    Role,
+
+    /// Human-readable name of the role, in the chosen language
+    RoleLabel,
+
+    /// State of the enrollment, like active or completed
+    EnrollmentState,
+
+    /// When the enrollment was created in Canvas
+    CreatedAt,
+
+    /// When the enrollment was last changed in Canvas
+    UpdatedAt,
+
+    /// Last time the user did something in the course room
+    LastActivityAt,
+
+    /// SIS ID of the section
+    Section,
+
+    /// Primary e-mail of the user in Canvas, or the one built with the mail
+    /// template if there is none
+    Mail1,
+
+    /// Login ID of the user
+    Mail2,
+
+    /// `true` if the e-mail in Canvas is not the one built with the mail
+    /// template. Empty if one of them is missing
+    MailMismatch,
+
+    /// Periods of the course round. Only in course rooms
+    Period,
+
+    /// Date of the exam. Only in exam rooms
+    ExamDate,
+
+    /// Courses examined in the exam. Only in exam rooms
+    CourseCodes,
+
+    /// Name of the exam, like TEN1. Only in exam rooms
+    Activity,
+
+    /// Section of the exam room: `regular` or `funka` (for students with
+    /// adapted exam conditions). Only in exam rooms
+    SectionType,
+}
+
+/// Sections of an exam room
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionType {
+    /// `AKT.<uid>`
+    Regular,
+
+    /// `AKT.<uid>.FUNKA`, for students with adapted exam conditions
+    Funka,
+}
+
+impl Column {
+    /// Columns written for course rooms if none are chosen
+    pub const COURSE_ROOMS: &'static [Column] = &[
+        Column::Name,
+        Column::UserId,
+        Column::Course,
+        Column::Role,
+        Column::RoleLabel,
+        Column::EnrollmentState,
+        Column::Section,
+        Column::Mail1,
+        Column::Mail2,
+        Column::MailMismatch,
+        Column::Period,
+    ];
+
+    /// Columns written for exam rooms if none are chosen
+    pub const EXAM_ROOMS: &'static [Column] = &[
+        Column::Name,
+        Column::UserId,
+        Column::Course,
+        Column::Role,
+        Column::RoleLabel,
+        Column::EnrollmentState,
+        Column::Section,
+        Column::Mail1,
+        Column::Mail2,
+        Column::MailMismatch,
+        Column::ExamDate,
+        Column::CourseCodes,
+        Column::Activity,
+        Column::SectionType,
+    ];
+
+    /// Returns the name of the column, as written in the header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::UserId => "user_id",
+            Column::Course => "course",
+            Column::Role => "role",
+            Column::RoleLabel => "role_label",
+            Column::EnrollmentState => "enrollment_state",
+            Column::CreatedAt => "created_at",
+            Column::UpdatedAt => "updated_at",
+            Column::LastActivityAt => "last_activity_at",
+            Column::Section => "section",
+            Column::Mail1 => "mail1",
+            Column::Mail2 => "mail2",
+            Column::MailMismatch => "mail_mismatch",
+            Column::Period => "period",
+            Column::ExamDate => "exam_date",
+            Column::CourseCodes => "course_codes",
+            Column::Activity => "activity",
+            Column::SectionType => "section_type",
+        }
+    }
+}
+
+/// An enrollment and where it comes from: a course round or an exam
+pub struct Row<'a> {
+    pub enrollment: &'a Enrollment,
+    pub course: &'a str,
+
+    /// Human-readable name of the role
+    pub role_label: &'a str,
+
+    /// Primary e-mail of the user in Canvas
+    pub canvas_email: Option<&'a str>,
+    pub mail_template: &'a MailTemplate,
+
+    /// Periods of the course round. Only in course rooms
+    pub period: Option<&'a str>,
+
+    /// The exam. Only in exam rooms
+    pub activity: Option<&'a Aktivitetstillfalle>,
+
+    /// Section of the exam room where the enrollment is. Only in exam rooms
+    pub section_type: Option<SectionType>,
+}
+
+impl<'a> Row<'a> {
+    /// Returns the value of a column. Missing values are written as `??`,
+    /// and columns that do not apply to the row are empty
+    pub fn value(&self, column: Column) -> String {
+        let enrollment = self.enrollment;
+
+        match column {
+            Column::Name => or_unknown(enrollment.user.sortable_name.as_deref()),
+            Column::UserId => enrollment.user_id.to_string(),
+            Column::Course => self.course.to_string(),
+            Column::Role => enrollment.role.clone(),
+            Column::RoleLabel => self.role_label.to_string(),
+            Column::EnrollmentState => enrollment.enrollment_state.as_str().to_string(),
+            Column::CreatedAt => timestamp(enrollment.created_at),
+            Column::UpdatedAt => timestamp(enrollment.updated_at),
+            Column::LastActivityAt => timestamp(enrollment.last_activity_at),
+            Column::Section => or_unknown(enrollment.sis_section_id.as_deref()),
+            Column::Mail1 => match self.canvas_email {
+                Some(email) => email.to_string(),
+                None => or_unknown(self.mail_template.derive(enrollment).as_deref()),
+            },
+            Column::Mail2 => or_unknown(enrollment.user.login_id.as_deref()),
+            Column::MailMismatch => {
+                match (self.canvas_email, self.mail_template.derive(enrollment)) {
+                    (Some(email), Some(derived)) => {
+                        (!email.eq_ignore_ascii_case(&derived)).to_string()
+                    }
+                    _ => String::new(),
+                }
+            }
+            Column::Period => self.period.unwrap_or_default().to_string(),
+            Column::ExamDate => self
+                .activity
+                .and_then(|a| a.date)
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            Column::CourseCodes => self
+                .activity
+                .map(|a| a.course_codes.join(" "))
+                .unwrap_or_default(),
+            Column::Activity => self
+                .activity
+                .and_then(|a| a.name.clone())
+                .unwrap_or_default(),
+            Column::SectionType => match self.section_type {
+                Some(SectionType::Regular) => "regular".to_string(),
+                Some(SectionType::Funka) => "funka".to_string(),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// Chosen columns of a row. It is serialized as a map from the column names
+/// to their values
+pub struct Record<'a> {
+    pub columns: &'a [Column],
+    pub row: &'a Row<'a>,
+}
+
+impl<'a> Serialize for Record<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+
+        for column in self.columns {
+            map.serialize_entry(column.name(), &self.row.value(*column))?;
+        }
+
+        map.end()
+    }
+}
+
+fn or_unknown(value: Option<&str>) -> String {
+    value.unwrap_or("??").to_string()
+}
+
+/// Writes a timestamp in RFC 3339, or nothing if it is missing
+fn timestamp(value: Option<DateTime<Utc>>) -> String {
+    value.map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas_api::{self, User};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn writes_exam_columns() {
+        let enrollment = Enrollment {
+            sis_user_id: Some("u1abcdef".to_string()),
+            sis_section_id: Some("AKT.abc.FUNKA".to_string()),
+            created_at: Some("2021-01-10T08:00:00Z".parse().unwrap()),
+            user: User {
+                sortable_name: Some("Doe, Alex".to_string()),
+                login_id: None,
+            },
+            ..canvas_api::enrollment(10)
+        };
+        let activity = Aktivitetstillfalle {
+            ladok_uid: "abc".to_string(),
+            date: Some(NaiveDate::from_ymd(2021, 3, 15)),
+            name: Some("TEN1".to_string()),
+            activity_type: None,
+            course_codes: vec!["SF1624".to_string(), "SF1625".to_string()],
+            students: vec![],
+        };
+        let row = Row {
+            enrollment: &enrollment,
+            course: "AKT.abc",
+            role_label: "Student",
+            canvas_email: None,
+            mail_template: &MailTemplate::default(),
+            period: None,
+            activity: Some(&activity),
+            section_type: Some(SectionType::Funka),
+        };
+        let values: Vec<String> = Column::EXAM_ROOMS.iter().map(|c| row.value(*c)).collect();
+
+        assert_eq!(
+            values,
+            vec![
+                "Doe, Alex",
+                "10",
+                "AKT.abc",
+                "StudentEnrollment",
+                "Student",
+                "active",
+                "AKT.abc.FUNKA",
+                "u1abcdef@kth.se",
+                "??",
+                "",
+                "2021-03-15",
+                "SF1624 SF1625",
+                "TEN1",
+                "funka"
+            ]
+        );
+        assert_eq!(row.value(Column::Period), "");
+        assert_eq!(row.value(Column::CreatedAt), "2021-01-10T08:00:00+00:00");
+        assert_eq!(row.value(Column::LastActivityAt), "");
+
+        let row = Row {
+            canvas_email: Some("alex.doe@example.com"),
+            ..row
+        };
+        assert_eq!(row.value(Column::Mail1), "alex.doe@example.com");
+        assert_eq!(row.value(Column::MailMismatch), "true");
+    }
+}
@@ -0,0 +1,290 @@
+use crate::canvas_api::Enrollment;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use table_output::Format;
+
+/// Column that identifies the users in an export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserKey {
+    /// `user_id`, the ID of the user in Canvas
+    UserId,
+
+    /// `mail2`, the login ID. Used in exports without `user_id`
+    LoginId,
+}
+
+impl UserKey {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserKey::UserId => "user_id",
+            UserKey::LoginId => "mail2",
+        }
+    }
+
+    /// Returns the value of the key for the user of an enrollment, as written
+    /// in the exports
+    fn of(&self, enrollment: &Enrollment) -> String {
+        match self {
+            UserKey::UserId => enrollment.user_id.to_string(),
+            UserKey::LoginId => enrollment
+                .user
+                .login_id
+                .clone()
+                .unwrap_or_else(|| "??".to_string()),
+        }
+    }
+}
+
+/// Enrollments at some moment, by user and section
+#[derive(Debug)]
+pub struct Snapshot {
+    pub key: UserKey,
+
+    /// Name and roles of every user and section
+    enrollments: BTreeMap<(String, String), (String, BTreeSet<String>)>,
+}
+
+impl Snapshot {
+    pub fn new(key: UserKey) -> Snapshot {
+        Snapshot {
+            key,
+            enrollments: BTreeMap::new(),
+        }
+    }
+
+    /// Reads an export of `list_enrollments`. Only CSV files can be read
+    pub fn read(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+        match Format::from_path(path) {
+            None | Some(Format::Csv) => {}
+            Some(format) => {
+                return Err(format!(
+                    "`{}` is {}. Only CSV can be compared",
+                    path.display(),
+                    format
+                )
+                .into())
+            }
+        }
+
+        let file = std::fs::File::open(path)
+            .map_err(|err| format!("Cannot open `{}`: {}", path.display(), err))?;
+        Snapshot::read_csv(file)
+    }
+
+    /// Reads an export in CSV. It must have the `section` and `role` columns,
+    /// and `user_id` or `mail2`
+    pub fn read_csv<R: io::Read>(reader: R) -> Result<Snapshot, Box<dyn Error>> {
+        let mut rdr = csv::Reader::from_reader(reader);
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h == name);
+
+        let (key, user) = match (column("user_id"), column("mail2")) {
+            (Some(i), _) => (UserKey::UserId, i),
+            (None, Some(i)) => (UserKey::LoginId, i),
+            (None, None) => {
+                return Err("The export has no user_id or mail2 column. Is it --by-user?".into())
+            }
+        };
+        let section = column("section").ok_or("The export has no section column")?;
+        let role = column("role").ok_or("The export has no role column")?;
+        let name = column("name");
+
+        let mut snapshot = Snapshot::new(key);
+
+        for record in rdr.records() {
+            let record = record?;
+            let value = |i: usize| record.get(i).unwrap_or_default().to_string();
+
+            snapshot.add(
+                value(user),
+                name.map(value).unwrap_or_default(),
+                value(section),
+                value(role),
+            );
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn add(&mut self, user: String, name: String, section: String, role: String) {
+        self.enrollments
+            .entry((user, section))
+            .or_insert_with(|| (name, BTreeSet::new()))
+            .1
+            .insert(role);
+    }
+
+    /// Adds an enrollment from Canvas
+    pub fn add_enrollment(&mut self, enrollment: &Enrollment) {
+        self.add(
+            self.key.of(enrollment),
+            enrollment
+                .user
+                .sortable_name
+                .clone()
+                .unwrap_or_else(|| "??".to_string()),
+            enrollment
+                .sis_section_id
+                .clone()
+                .unwrap_or_else(|| "??".to_string()),
+            enrollment.role.clone(),
+        );
+    }
+
+    /// Returns the SIS IDs of the sections, without repetitions
+    pub fn sections(&self) -> BTreeSet<&str> {
+        self.enrollments
+            .keys()
+            .map(|(_, section)| section.as_str())
+            .filter(|section| *section != "??")
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    RoleChanged,
+}
+
+/// A difference between two snapshots, for one user in one section
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Change {
+    pub change: ChangeKind,
+    pub user: String,
+    pub name: String,
+    pub section: String,
+
+    /// Roles before, separated by spaces. Empty if the user was added
+    pub old_roles: String,
+
+    /// Roles after, separated by spaces. Empty if the user was removed
+    pub new_roles: String,
+}
+
+impl Change {
+    /// Names of the fields of [`Change`]
+    pub const HEADERS: &'static [&'static str] = &[
+        "change",
+        "user",
+        "name",
+        "section",
+        "old_roles",
+        "new_roles",
+    ];
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let who = format!("{} ({}) in {}", self.name, self.user, self.section);
+
+        match self.change {
+            ChangeKind::Added => write!(f, "+ {} as {}", who, self.new_roles),
+            ChangeKind::Removed => write!(f, "- {} as {}", who, self.old_roles),
+            ChangeKind::RoleChanged => {
+                write!(f, "~ {}: {} -> {}", who, self.old_roles, self.new_roles)
+            }
+        }
+    }
+}
+
+/// Returns the users added, removed and with other roles in every section,
+/// sorted by user and section
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<Change> {
+    let keys: BTreeSet<_> = old
+        .enrollments
+        .keys()
+        .chain(new.enrollments.keys())
+        .collect();
+    let roles = |roles: &BTreeSet<String>| roles.iter().cloned().collect::<Vec<_>>().join(" ");
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let (change, (name, _)) = match (old.enrollments.get(key), new.enrollments.get(key)) {
+                (Some(before), Some(after)) if before.1 == after.1 => return None,
+                (Some(_), Some(after)) => (ChangeKind::RoleChanged, after),
+                (Some(before), None) => (ChangeKind::Removed, before),
+                (None, Some(after)) => (ChangeKind::Added, after),
+                (None, None) => return None,
+            };
+
+            Some(Change {
+                change,
+                user: key.0.clone(),
+                name: name.clone(),
+                section: key.1.clone(),
+                old_roles: old
+                    .enrollments
+                    .get(key)
+                    .map(|e| roles(&e.1))
+                    .unwrap_or_default(),
+                new_roles: new
+                    .enrollments
+                    .get(key)
+                    .map(|e| roles(&e.1))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Returns a line like "2 added, 1 removed, 0 with other roles"
+pub fn summary(changes: &[Change]) -> String {
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.change == kind).count();
+
+    format!(
+        "{} added, {} removed, {} with other roles",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::RoleChanged)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_exports() {
+        let old = Snapshot::read_csv(
+            "name,user_id,role,section\n\
+             \"Doe, Alex\",1,StudentEnrollment,SF1624VT211\n\
+             \"Roe, Kim\",2,StudentEnrollment,SF1624VT211\n\
+             \"Poe, Sam\",3,TaEnrollment,SF1624VT211\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let new = Snapshot::read_csv(
+            "name,user_id,role,section\n\
+             \"Doe, Alex\",1,StudentEnrollment,SF1624VT211\n\
+             \"Poe, Sam\",3,TeacherEnrollment,SF1624VT211\n\
+             \"Poe, Sam\",3,TaEnrollment,SF1624VT211\n\
+             \"Roe, Kim\",2,StudentEnrollment,SF1625VT211\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(old.key, UserKey::UserId);
+
+        let changes = diff(&old, &new);
+        let lines: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "- Roe, Kim (2) in SF1624VT211 as StudentEnrollment",
+                "+ Roe, Kim (2) in SF1625VT211 as StudentEnrollment",
+                "~ Poe, Sam (3) in SF1624VT211: TaEnrollment -> TaEnrollment TeacherEnrollment",
+            ]
+        );
+        assert_eq!(summary(&changes), "1 added, 1 removed, 1 with other roles");
+
+        let by_login = Snapshot::read_csv("mail2,role,section\nalex@kth.se,Student,X\n".as_bytes());
+        assert_eq!(by_login.unwrap().key, UserKey::LoginId);
+        assert!(Snapshot::read_csv("name,courses,roles\n".as_bytes()).is_err());
+    }
+}
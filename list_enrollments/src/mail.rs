@@ -0,0 +1,124 @@
+use crate::canvas_api::{self, Enrollment, EnrollmentState};
+use ::canvas_api::CanvasApi;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Builds the e-mail of a user from their IDs, like `{sis_user_id}@kth.se`.
+/// Used for users without e-mail in Canvas.
+///
+/// Placeholders: `{sis_user_id}` and `{login_id}`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MailTemplate(String);
+
+const PLACEHOLDERS: &[&str] = &["{sis_user_id}", "{login_id}"];
+
+impl MailTemplate {
+    /// Returns the e-mail of the user of an enrollment, or `None` if the
+    /// user does not have some ID in the template
+    pub fn derive(&self, enrollment: &Enrollment) -> Option<String> {
+        let mut mail = self.0.clone();
+
+        if mail.contains("{sis_user_id}") {
+            mail = mail.replace("{sis_user_id}", enrollment.sis_user_id.as_deref()?);
+        }
+
+        if mail.contains("{login_id}") {
+            mail = mail.replace("{login_id}", enrollment.user.login_id.as_deref()?);
+        }
+
+        Some(mail)
+    }
+}
+
+impl Default for MailTemplate {
+    fn default() -> Self {
+        MailTemplate("{sis_user_id}@kth.se".to_string())
+    }
+}
+
+impl FromStr for MailTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MailTemplate, String> {
+        if PLACEHOLDERS.iter().any(|p| s.contains(p)) {
+            Ok(MailTemplate(s.to_string()))
+        } else {
+            Err(format!(
+                "`{}` has no placeholders. Use {}",
+                s,
+                PLACEHOLDERS.join(" or ")
+            ))
+        }
+    }
+}
+
+/// Primary e-mails of the users in Canvas. They are requested once per
+/// course, for all its users with enrollments in the listed states
+pub struct CanvasEmails {
+    states: Vec<EnrollmentState>,
+    by_course: HashMap<u64, HashMap<u64, String>>,
+}
+
+impl CanvasEmails {
+    pub fn new(states: &[EnrollmentState]) -> CanvasEmails {
+        CanvasEmails {
+            states: states.to_vec(),
+            by_course: HashMap::new(),
+        }
+    }
+
+    /// Returns the primary e-mail in Canvas of the user of an enrollment.
+    /// Fails if the e-mails of the course cannot be requested
+    pub fn get(
+        &mut self,
+        canvas: &CanvasApi,
+        enrollment: &Enrollment,
+    ) -> Result<Option<&str>, Box<dyn std::error::Error>> {
+        let course_id = enrollment.course_id;
+
+        if !self.by_course.contains_key(&course_id) {
+            let emails = canvas_api::get_emails(canvas, course_id, &self.states)?;
+            self.by_course.insert(course_id, emails);
+        }
+
+        Ok(self.by_course[&course_id]
+            .get(&enrollment.user_id)
+            .map(String::as_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas_api::User;
+
+    #[test]
+    fn derives_emails() {
+        let enrollment = Enrollment {
+            sis_user_id: Some("u1abcdef".to_string()),
+            user: User {
+                sortable_name: None,
+                login_id: Some("alex@kth.se".to_string()),
+            },
+            ..canvas_api::enrollment(10)
+        };
+
+        assert_eq!(
+            MailTemplate::default().derive(&enrollment),
+            Some("u1abcdef@kth.se".to_string())
+        );
+
+        let template: MailTemplate = "{login_id}".parse().unwrap();
+        assert_eq!(
+            template.derive(&enrollment),
+            Some("alex@kth.se".to_string())
+        );
+
+        let guest = Enrollment {
+            sis_user_id: None,
+            ..enrollment
+        };
+        assert_eq!(MailTemplate::default().derive(&guest), None);
+        assert!("admin@kth.se".parse::<MailTemplate>().is_err());
+    }
+}
@@ -1,21 +1,120 @@
-mod akt_api;
+mod by_user;
 mod canvas_api;
-mod kopps_api;
+mod columns;
+mod diff;
+mod mail;
+mod output;
+mod roles;
+use ::canvas_api::config::{self, Config, Profile};
+use ::canvas_api::courses::CanvasId;
+use ::canvas_api::terms::{self, Semester, Term, TermMapping};
+use ::canvas_api::{CanvasApi, ResponseCache};
+use akt_api::AktApi;
+use canvas_api::{Enrollment, EnrollmentState};
 use chrono::NaiveDate;
-use csv::Writer;
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use columns::{Column, Row, SectionType};
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect, Select};
+use diff::{Change, Snapshot};
 use dotenv::dotenv;
-use serde::Serialize;
+use kopps_api::{KoppsApi, OfferingsQuery, Period, PeriodMatch};
+use mail::CanvasEmails;
+use output::{OutputArgs, OutputOptions};
+use roles::RoleFilter;
 use std::env;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::time::Duration;
+use table_output::{Format, TableWriter};
+
+/// Lists the enrollments of Canvas course rooms and exam rooms in a CSV,
+/// JSON, NDJSON or XLSX file.
+///
+/// Missing arguments are prompted when running in a terminal.
+#[derive(Parser)]
+#[command(about)]
+struct Cli {
+    /// Canvas profile from the configuration file
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// States of the enrollments to list, like active,invited,completed.
+    /// Add completed and inactive to include concluded and deactivated
+    /// enrollments
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [EnrollmentState::Active, EnrollmentState::Invited]
+    )]
+    enrollment_state: Vec<EnrollmentState>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-#[derive(Serialize)]
-struct Row<'a> {
-    name: &'a str,
-    course: &'a str,
-    role: &'a str,
-    section: &'a str,
-    mail1: &'a str,
-    mail2: &'a str,
+#[derive(Subcommand)]
+enum Command {
+    /// Enrollments in course rooms of the course rounds that start in some
+    /// periods
+    CourseRooms {
+        /// Year, like 2021
+        #[arg(long)]
+        year: Option<i32>,
+
+        /// Term: VT (spring) or HT (autumn)
+        #[arg(long, value_parser = parse_term)]
+        term: Option<Term>,
+
+        /// Periods, like P3 or P3,P4. Spring has P3, P4 and P5. Autumn has
+        /// P0, P1 and P2
+        #[arg(long, value_delimiter = ',')]
+        period: Vec<String>,
+
+        /// All the periods of the term
+        #[arg(long, conflicts_with = "period")]
+        all_periods: bool,
+
+        #[command(flatten)]
+        filters: RoundFilters,
+    },
+
+    /// Enrollments in exam rooms of the exams between two dates
+    ExamRooms {
+        /// First date, like 2021-03-15
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Last date, included
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        /// Timeout, in seconds, of the request to the aktivitetstillfällen
+        /// API
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+
+        /// Write also a file with only the enrollments in FUNKA sections
+        /// (students with adapted exam conditions)
+        #[arg(long)]
+        funka_report: Option<PathBuf>,
+    },
+
+    /// Compares two CSV exports: users added, removed or with other roles in
+    /// every section
+    Diff {
+        /// The old export
+        old: PathBuf,
+
+        /// The new export. If missing, the current enrollments in Canvas of
+        /// the sections in the old export
+        new: Option<PathBuf>,
+    },
 }
 
 enum UserChoice {
@@ -23,85 +122,361 @@ enum UserChoice {
     ExamRoomEnrollments,
 }
 
+/// Filters for the course rounds
+#[derive(Args, Default)]
+struct RoundFilters {
+    /// Include rounds that run during the period, not only the ones that
+    /// start in it
+    #[arg(long)]
+    running: bool,
+
+    /// School code, like EECS
+    #[arg(long)]
+    school: Option<String>,
+
+    /// Department code, like JH
+    #[arg(long)]
+    department: Option<String>,
+
+    /// State of the round, like APPROVED
+    #[arg(long)]
+    state: Option<String>,
+}
+
 fn main() {
     dotenv().ok();
+    let cli = Cli::parse();
+
+    let command = match cli.command {
+        Some(command) => command,
+        None => {
+            require_terminal("A subcommand (course-rooms or exam-rooms)");
+
+            match prompt_choice() {
+                UserChoice::CourseRoomEnrollments => Command::CourseRooms {
+                    year: None,
+                    term: None,
+                    period: vec![],
+                    all_periods: false,
+                    filters: RoundFilters::default(),
+                },
+                UserChoice::ExamRoomEnrollments => Command::ExamRooms {
+                    from: None,
+                    to: None,
+                    timeout: 300,
+                    funka_report: None,
+                },
+            }
+        }
+    };
 
-    match prompt_choice() {
-        UserChoice::CourseRoomEnrollments => list_course_room_enrollments(),
-        UserChoice::ExamRoomEnrollments => list_exam_room_enrollments(),
+    match command {
+        Command::CourseRooms {
+            year,
+            term,
+            period,
+            all_periods,
+            filters,
+        } => {
+            let (semester, periods) = year_term_periods(year, term, period, all_periods);
+            let query = OfferingsQuery {
+                periods,
+                period_match: match filters.running {
+                    true => PeriodMatch::RunsIn,
+                    false => PeriodMatch::Starts,
+                },
+                school: filters.school,
+                department: filters.department,
+                state: filters.state,
+                ..OfferingsQuery::semester(semester)
+            };
+            let profile = canvas_profile(cli.profile.as_deref());
+            let output = OutputOptions::new(cli.output, Column::COURSE_ROOMS);
+            list_course_room_enrollments(&profile, &query, &cli.enrollment_state, &output);
+        }
+        Command::ExamRooms {
+            from,
+            to,
+            timeout,
+            funka_report,
+        } => {
+            let start_date = from.unwrap_or_else(|| {
+                require_terminal("--from");
+                prompt_date("Enter the start date")
+            });
+            let end_date = to.unwrap_or_else(|| {
+                require_terminal("--to");
+                prompt_date("Enter the end date")
+            });
+            let profile = canvas_profile(cli.profile.as_deref());
+            let timeout = Duration::from_secs(timeout);
+            let output = OutputOptions::new(cli.output, Column::EXAM_ROOMS);
+            list_exam_room_enrollments(
+                &profile,
+                start_date,
+                end_date,
+                timeout,
+                &cli.enrollment_state,
+                &output,
+                funka_report,
+            );
+        }
+        Command::Diff { old, new } => {
+            let output = OutputOptions::new(cli.output, &[]);
+            let old = Snapshot::read(&old).expect("Error when reading the old export");
+            let new = match new {
+                Some(new) => Snapshot::read(&new).expect("Error when reading the new export"),
+                None => {
+                    let profile = canvas_profile(cli.profile.as_deref());
+                    current_enrollments(&profile, &old, &cli.enrollment_state, &output.roles)
+                }
+            };
+            diff_enrollments(&old, &new, &output);
+        }
     }
 }
 
-fn list_course_room_enrollments() {
-    let kopps_api_url = env("KOPPS_API_URL");
-    let canvas_api_url = env("CANVAS_API_URL");
-    let canvas_api_token = env("CANVAS_API_TOKEN");
-    let (year_term, period) = prompt_year_term_period();
+fn list_course_room_enrollments(
+    profile: &Profile,
+    query: &OfferingsQuery,
+    states: &[EnrollmentState],
+    output: &OutputOptions,
+) {
+    let kopps = KoppsApi::new(&env("KOPPS_API_URL")).expect("Invalid KOPPS_API_URL");
+    let canvas = canvas_client(profile);
+    let mut emails = CanvasEmails::new(states);
 
-    let file_path = format!("enrollments-courserooms-{}-{}.csv", year_term, period);
+    if let Some(account_id) = profile.account_id {
+        print_canvas_term(&canvas, account_id, &query.semester);
+    }
 
-    println!("Fetching data from Kopps API");
-    let course_rounds = kopps_api::get_course_rounds(&kopps_api_url, &year_term, &period)
-        .filter(|round| round.first_period == format!("{}{}", year_term, period));
+    let periods = match query.periods.is_empty() {
+        true => "all".to_string(),
+        false => query
+            .periods
+            .iter()
+            .map(|p| format!("P{}", p.number()))
+            .collect::<Vec<_>>()
+            .join("-"),
+    };
 
-    println!("Writing to the file `{}`", file_path);
+    println!("Fetching data from Kopps API");
+    let course_rounds = kopps
+        .course_rounds(query)
+        .expect("Error when getting course rounds from Kopps");
 
-    let mut wtr = Writer::from_path(file_path).expect("Error when creating the file");
+    let mut wtr = output.create(&format!(
+        "enrollments-courserooms-{}-{}",
+        query.semester.kopps_code(),
+        periods
+    ));
 
     for round in course_rounds {
-        let sis_id = kopps_api::make_sis_id(&round);
-        println!("Round {}", &sis_id);
-        let enrollments = canvas_api::get_enrollments(&canvas_api_url, &canvas_api_token, &sis_id)
+        let sis_id = round.sis_id();
+        let periods = round
+            .matching_periods(query)
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("Round {} ({})", &sis_id, periods);
+        let enrollments = canvas_api::get_enrollments(&canvas, &sis_id, states)
             .expect("Error when getting enrollments");
 
-        for enrollment in enrollments.into_iter() {
-            write_enrollment(
-                &mut wtr,
-                &format!("{} {}", sis_id, round.first_period),
+        let course = format!("{} {}", sis_id, round.first_period);
+
+        for enrollment in &enrollments {
+            let row = Row {
                 enrollment,
-            );
+                course: &course,
+                canvas_email: canvas_email(&canvas, &mut emails, output, enrollment),
+                mail_template: &output.mail_template,
+                role_label: output.role_label(enrollment),
+                period: Some(&periods),
+                activity: None,
+                section_type: None,
+            };
+            wtr.write(&row);
         }
     }
+
+    wtr.finish();
 }
 
-fn list_exam_room_enrollments() {
-    let canvas_api_url = env("CANVAS_API_URL");
-    let canvas_api_token = env("CANVAS_API_TOKEN");
+fn list_exam_room_enrollments(
+    profile: &Profile,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    timeout: Duration,
+    states: &[EnrollmentState],
+    output: &OutputOptions,
+    funka_report: Option<PathBuf>,
+) {
+    let canvas = canvas_client(profile);
+    let mut emails = CanvasEmails::new(states);
+    let akt = AktApi::new(
+        &env("AKTIVITETSTILLFALLEN_API_URL"),
+        &env("AKTIVITETSTILLFALLEN_API_TOKEN"),
+    )
+    .expect("Invalid AKTIVITETSTILLFALLEN_API_URL")
+    .timeout(timeout);
+
+    let mut wtr = output.create(&format!(
+        "enrollments-examrooms-{}---{}",
+        start_date, end_date
+    ));
+    let mut funka_wtr = funka_report.map(|file_path| output.create_at(&file_path));
+
+    println!("Getting activities from {} to {}", start_date, end_date);
+    let aktivitetstillfallen = akt
+        .activities(start_date, end_date)
+        .expect("Error when getting activities");
+
+    for round in aktivitetstillfallen {
+        println!("- Activity {}", &round.ladok_uid);
+        let course = round.sis_id();
+
+        let sections = [
+            (round.sis_id(), SectionType::Regular),
+            (round.funka_sis_id(), SectionType::Funka),
+        ];
+
+        for (sis_id, section_type) in &sections {
+            let enrollments = canvas_api::get_enrollments(&canvas, sis_id, states)
+                .expect("Error when getting enrollments");
+
+            for enrollment in &enrollments {
+                let row = Row {
+                    enrollment,
+                    course: &course,
+                    canvas_email: canvas_email(&canvas, &mut emails, output, enrollment),
+                    mail_template: &output.mail_template,
+                    role_label: output.role_label(enrollment),
+                    period: None,
+                    activity: Some(&round),
+                    section_type: Some(*section_type),
+                };
+                wtr.write(&row);
+
+                if let (Some(funka_wtr), SectionType::Funka) = (&mut funka_wtr, section_type) {
+                    funka_wtr.write(&row);
+                }
+            }
+        }
+    }
 
-    let akt_api_url = env("AKTIVITETSTILLFALLEN_API_URL");
-    let akt_api_token = env("AKTIVITETSTILLFALLEN_API_TOKEN");
+    wtr.finish();
 
-    let start_date = prompt_date("Enter the start date");
-    let end_date = prompt_date("Enter the end date");
+    if let Some(funka_wtr) = funka_wtr {
+        funka_wtr.finish();
+    }
+}
 
-    let dates_range = start_date
-        .iter_days()
-        .take((end_date - start_date).num_days() as usize + 1);
+/// Returns the enrollments in Canvas of the sections of an export, with the
+/// chosen states and roles. Sections that are not in the export are not
+/// requested
+fn current_enrollments(
+    profile: &Profile,
+    export: &Snapshot,
+    states: &[EnrollmentState],
+    roles: &RoleFilter,
+) -> Snapshot {
+    let canvas = canvas_client(profile);
+    let mut snapshot = Snapshot::new(export.key);
+
+    for section in export.sections() {
+        println!("Section {}", section);
+        let enrollments = canvas_api::get_enrollments(&canvas, section, states)
+            .expect("Error when getting enrollments");
 
-    let file_path = format!("enrollments-examrooms-{}---{}.csv", start_date, end_date);
-    let mut wtr = Writer::from_path(file_path).expect("Error when writing a file");
+        for enrollment in enrollments.iter().filter(|e| roles.matches(e)) {
+            snapshot.add_enrollment(enrollment);
+        }
+    }
 
-    for date in dates_range {
-        println!("Getting activities for {}", date);
-        let aktivitetstillfallen =
-            akt_api::get_aktivitetstillfallen(&akt_api_url, &akt_api_token, &date);
+    snapshot
+}
 
-        for round in aktivitetstillfallen {
-            println!("- Activity {}", &round.ladok_uid);
-            let sis_id1 = format!("AKT.{}", &round.ladok_uid);
-            let sis_id2 = format!("AKT.{}.FUNKA", &round.ladok_uid);
-            let mut enrollments =
-                canvas_api::get_enrollments(&canvas_api_url, &canvas_api_token, &sis_id1)
-                    .expect("Error when getting enrollments");
+/// Prints the differences between two snapshots and writes them to a file
+fn diff_enrollments(old: &Snapshot, new: &Snapshot, output: &OutputOptions) {
+    if old.key != new.key {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "The exports identify users with different columns: {} and {}",
+                    old.key.name(),
+                    new.key.name()
+                ),
+            )
+            .exit();
+    }
 
-            enrollments.append(
-                &mut canvas_api::get_enrollments(&canvas_api_url, &canvas_api_token, &sis_id2)
-                    .expect("Error when getting enrollments"),
-            );
+    let changes = diff::diff(old, new);
 
-            for enrollment in enrollments.into_iter() {
-                write_enrollment(&mut wtr, &sis_id1, enrollment);
-            }
+    for change in &changes {
+        println!("{}", change);
+    }
+    println!("{}", diff::summary(&changes));
+
+    let path = output.path.clone().unwrap_or_else(|| {
+        let format = output.format.unwrap_or(Format::Csv);
+        PathBuf::from(format!("enrollments-diff.{}", format.extension()))
+    });
+    println!("Writing to the file `{}`", path.display());
+
+    let mut wtr = TableWriter::create(&path, output.format).expect("Error when creating the file");
+    wtr.headers(Change::HEADERS)
+        .expect("Error when writing the header");
+    for change in &changes {
+        wtr.serialize(change).expect("Error when writing a row");
+    }
+    wtr.finish().expect("Error when writing the file");
+}
+
+/// Returns the Canvas profile to use. It is read from the configuration file
+/// if a profile is chosen (with `--profile` or `CANVAS_PROFILE`) or the file
+/// exists. Otherwise it is built from the `CANVAS_API_URL` and
+/// `CANVAS_API_TOKEN` (or `CANVAS_API_TOKEN_COMMAND`, `CANVAS_API_TOKEN_FILE`)
+/// environmental variables.
+fn canvas_profile(name: Option<&str>) -> Profile {
+    let name = name.map(String::from).or_else(config::selected_profile);
+
+    if name.is_none() && !config::config_path().exists() {
+        return Profile::from_env().unwrap_or_else(|| {
+            println!("Environmental variables CANVAS_API_URL and CANVAS_API_TOKEN not defined");
+            panic!("Environmental variables CANVAS_API_URL and CANVAS_API_TOKEN not defined");
+        });
+    }
+
+    let profile = Config::load_default()
+        .and_then(|config| match &name {
+            Some(name) => config.profile(name),
+            None => config.selected(),
+        })
+        .expect("Error when reading the Canvas profile");
+
+    println!("Using Canvas profile `{}` ({})", profile.name, profile.url);
+    profile
+}
+
+/// Creates the Canvas client. Responses are cached in disk if the
+/// `CANVAS_CACHE_DIR` environmental variable is set
+fn canvas_client(profile: &Profile) -> CanvasApi<'_> {
+    let canvas = profile
+        .canvas_api()
+        .expect("Error when getting the Canvas token");
+
+    match env::var("CANVAS_CACHE_DIR") {
+        Err(_) => canvas,
+        Ok(directory) => {
+            let ttl = env::var("CANVAS_CACHE_TTL")
+                .ok()
+                .and_then(|ttl| ttl.parse::<u64>().ok())
+                .unwrap_or(24 * 3600);
+
+            println!("Caching Canvas responses in `{}`", directory);
+            canvas.cache(ResponseCache::new(directory, Duration::from_secs(ttl)))
         }
     }
 }
@@ -116,14 +491,116 @@ fn env(key: &str) -> String {
     }
 }
 
-// Prompt the year, term and period
-fn prompt_year_term_period() -> (String, String) {
-    let year: String = Input::with_theme(&ColorfulTheme::default())
+/// Prints the Canvas enrollment term that corresponds to a semester, or a
+/// warning if there is none. The term is only informative, so the export
+/// continues if it cannot be found
+fn print_canvas_term(canvas: &CanvasApi, account_id: u64, semester: &Semester) {
+    let mapping = match terms::list_terms(canvas, &CanvasId::Id(account_id)) {
+        Ok(terms) => TermMapping::new(terms),
+        Err(e) => {
+            println!("Warning: cannot get the Canvas enrollment terms: {}", e);
+            return;
+        }
+    };
+
+    match mapping.term_for(semester) {
+        Some(term) => println!(
+            "Semester {} is the Canvas term `{}` (ID {})",
+            semester, term.name, term.id
+        ),
+        None => println!("Warning: there is no Canvas term for {}", semester),
+    }
+}
+
+/// Exits with an error if the program does not run in a terminal. Called
+/// before prompting for an argument that is missing
+fn require_terminal(argument: &str) {
+    if !io::stdin().is_terminal() {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                format!("{} is required when not running in a terminal", argument),
+            )
+            .exit();
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    match term.to_uppercase().as_str() {
+        "VT" | "1" | "SPRING" => Ok(Term::Spring),
+        "HT" | "2" | "AUTUMN" | "FALL" => Ok(Term::Autumn),
+        _ => Err(format!("`{}` is not a term. Use VT or HT", term)),
+    }
+}
+
+/// Returns the semester and periods from the arguments, and prompts the ones
+/// that are missing. An empty list of periods means the whole semester
+fn year_term_periods(
+    year: Option<i32>,
+    term: Option<Term>,
+    periods: Vec<String>,
+    all_periods: bool,
+) -> (Semester, Vec<Period>) {
+    let year = year.unwrap_or_else(|| {
+        require_terminal("--year");
+        prompt_year()
+    });
+    let term = term.unwrap_or_else(|| {
+        require_terminal("--term");
+        prompt_term()
+    });
+    let semester = Semester::new(year, term);
+
+    if all_periods {
+        return (semester, vec![]);
+    }
+
+    if periods.is_empty() {
+        require_terminal("--period or --all-periods");
+        return (semester, prompt_periods(semester));
+    }
+
+    let periods = periods
+        .iter()
+        .map(|period| {
+            format!("{}{}", semester.kopps_code(), period.to_uppercase())
+                .parse()
+                .unwrap_or_else(|_| {
+                    Cli::command()
+                        .error(
+                            ErrorKind::InvalidValue,
+                            format!(
+                                "Period {} is not in {}. Choose among {}",
+                                period,
+                                semester,
+                                period_names(semester).join(", ")
+                            ),
+                        )
+                        .exit()
+                })
+        })
+        .collect();
+
+    (semester, periods)
+}
+
+/// Returns the names (like P3) of the periods of a semester
+fn period_names(semester: Semester) -> Vec<String> {
+    Period::all_in(semester)
+        .iter()
+        .map(|p| format!("P{}", p.number()))
+        .collect()
+}
+
+fn prompt_year() -> i32 {
+    Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Write a year")
         .with_initial_text("2020")
         .interact_text()
-        .expect("Failed to prompt year");
+        .expect("Failed to prompt year")
+}
 
+fn prompt_term() -> Term {
     let terms = vec!["VT (Spring)", "HT (Fall)"];
     let term_selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Choose a term")
@@ -132,24 +609,25 @@ fn prompt_year_term_period() -> (String, String) {
         .interact()
         .expect("Failed to get the term");
 
-    let (term, periods) = match term_selection {
-        0 => ("1", vec!["P3", "P4", "P5"]),
-        1 => ("2", vec!["P0", "P1", "P2"]),
+    match term_selection {
+        0 => Term::Spring,
+        1 => Term::Autumn,
         _ => {
             panic!("Unexpected value for term option");
         }
-    };
+    }
+}
 
-    let period_selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Choose a period")
-        .items(&periods)
-        .default(0)
+/// Prompts the periods of a semester. Choosing none means the whole semester
+fn prompt_periods(semester: Semester) -> Vec<Period> {
+    let periods = Period::all_in(semester);
+    let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Choose periods (none for the whole term)")
+        .items(&period_names(semester))
         .interact()
-        .expect("Failed to get the period");
-
-    let period = periods[period_selection];
+        .expect("Failed to get the periods");
 
-    (format!("{}{}", year, term), period.to_string())
+    selection.into_iter().map(|i| periods[i]).collect()
 }
 
 fn prompt_choice() -> UserChoice {
@@ -178,22 +656,17 @@ fn prompt_date(prompt: &str) -> NaiveDate {
         .expect("Failed to prompt date")
 }
 
-fn write_enrollment(
-    wtr: &mut Writer<std::fs::File>,
-    course_code: &str,
-    enrollment: canvas_api::Enrollment,
-) {
-    wtr.serialize(Row {
-        course: course_code,
-        name: enrollment.user.sortable_name.as_deref().unwrap_or("??"),
-        role: &enrollment.role,
-        section: enrollment.sis_section_id.as_deref().unwrap_or("??"),
-        mail1: enrollment
-            .sis_user_id
-            .map(|u| format!("{}@kth.se", u))
-            .as_deref()
-            .unwrap_or("??"),
-        mail2: enrollment.user.login_id.as_deref().unwrap_or("??"),
-    })
-    .expect("Error when writing a row");
+/// Returns the e-mail of the user in Canvas, if the output needs it
+fn canvas_email<'a>(
+    canvas: &CanvasApi,
+    emails: &'a mut CanvasEmails,
+    output: &OutputOptions,
+    enrollment: &Enrollment,
+) -> Option<&'a str> {
+    match output.needs_canvas_emails() {
+        true => emails
+            .get(canvas, enrollment)
+            .expect("Error when getting e-mails"),
+        false => None,
+    }
 }
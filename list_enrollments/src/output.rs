@@ -0,0 +1,197 @@
+use crate::by_user::UserSummaries;
+use crate::canvas_api::Enrollment;
+use crate::columns::{Column, Record, Row};
+use crate::mail::MailTemplate;
+use crate::roles::{Language, RoleFilter, RoleLabels, RoleMatch};
+use clap::Args;
+use std::path::{Path, PathBuf};
+use table_output::{Format, TableWriter};
+
+/// Arguments about the output file
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Path of the output file. By default, a name with the chosen period or
+    /// dates
+    #[arg(long, short, global = true)]
+    output: Option<PathBuf>,
+
+    /// Format of the output file: csv, json, ndjson or xlsx. By default, the
+    /// one of the extension of the file, or csv
+    #[arg(long, global = true)]
+    format: Option<Format>,
+
+    /// Columns of the output file, like name,course,exam_date. By default, all
+    /// the columns that apply to the chosen rooms
+    #[arg(long, global = true, value_delimiter = ',')]
+    columns: Vec<Column>,
+
+    /// E-mail of the users without e-mail in Canvas. Placeholders:
+    /// {sis_user_id} and {login_id}
+    #[arg(long, global = true, default_value = "{sis_user_id}@kth.se")]
+    mail_template: MailTemplate,
+
+    /// Write one row per user, with all their courses, sections and roles,
+    /// instead of one row per enrollment. The columns are fixed, so it
+    /// cannot be used with --columns
+    #[arg(long, global = true, conflicts_with = "columns")]
+    by_user: bool,
+
+    /// Write only the enrollments with these roles, like Teacher,TA
+    #[arg(long, global = true, value_delimiter = ',')]
+    role: Vec<String>,
+
+    /// Do not write the enrollments with these roles
+    #[arg(long, global = true, value_delimiter = ',')]
+    exclude_role: Vec<String>,
+
+    /// Compare --role and --exclude-role with the name of the role or with
+    /// its base role
+    #[arg(long, global = true, value_enum, default_value_t = RoleMatch::Name)]
+    role_match: RoleMatch,
+
+    /// TOML file with labels for the roles, in English (en) and Swedish (sv)
+    #[arg(long, global = true)]
+    role_labels: Option<PathBuf>,
+
+    /// Language of the role labels
+    #[arg(long, global = true, value_enum, default_value_t = Language::En)]
+    language: Language,
+}
+
+/// Where and how to write the enrollments
+pub struct OutputOptions {
+    /// Path of the file. If `None`, a default name
+    pub path: Option<PathBuf>,
+
+    /// If `None`, the one of the extension of the file
+    pub format: Option<Format>,
+    pub columns: Vec<Column>,
+    pub mail_template: MailTemplate,
+
+    /// Write one row per user instead of one per enrollment
+    pub by_user: bool,
+
+    pub roles: RoleFilter,
+    pub role_labels: RoleLabels,
+    pub language: Language,
+}
+
+/// Writes the enrollments to a file, one per row or aggregated by user
+pub struct EnrollmentWriter<'a> {
+    wtr: TableWriter,
+    options: &'a OutputOptions,
+
+    /// Enrollments aggregated so far, in the per-user mode
+    users: Option<UserSummaries>,
+}
+
+impl OutputOptions {
+    /// Reads the arguments. `default_columns` are used if no columns are
+    /// chosen
+    pub fn new(args: OutputArgs, default_columns: &[Column]) -> OutputOptions {
+        let role_labels = match &args.role_labels {
+            None => RoleLabels::default(),
+            Some(path) => RoleLabels::load(path).expect("Error when reading the role labels"),
+        };
+
+        OutputOptions {
+            path: args.output,
+            format: args.format,
+            columns: match args.columns.is_empty() {
+                true => default_columns.to_vec(),
+                false => args.columns,
+            },
+            mail_template: args.mail_template,
+            by_user: args.by_user,
+            roles: RoleFilter {
+                include: args.role,
+                exclude: args.exclude_role,
+                match_by: args.role_match,
+            },
+            role_labels,
+            language: args.language,
+        }
+    }
+
+    /// Returns `true` if the e-mails of the users must be requested to
+    /// Canvas
+    pub fn needs_canvas_emails(&self) -> bool {
+        self.by_user
+            || self
+                .columns
+                .iter()
+                .any(|c| *c == Column::Mail1 || *c == Column::MailMismatch)
+    }
+
+    /// Returns the label of the role of an enrollment
+    pub fn role_label<'a>(&'a self, enrollment: &'a Enrollment) -> &'a str {
+        self.role_labels.label(enrollment, self.language)
+    }
+
+    /// Creates the output file. `default_name` (without extension) is used if
+    /// no path is chosen
+    pub fn create(&self, default_name: &str) -> EnrollmentWriter<'_> {
+        let path = self.path.clone().unwrap_or_else(|| {
+            let format = self.format.unwrap_or(Format::Csv);
+            let suffix = if self.by_user { "-by-user" } else { "" };
+            PathBuf::from(format!("{}{}.{}", default_name, suffix, format.extension()))
+        });
+
+        self.create_at(&path)
+    }
+
+    /// Creates a file in `path` and writes the header
+    pub fn create_at(&self, path: &Path) -> EnrollmentWriter<'_> {
+        println!("Writing to the file `{}`", path.display());
+        let mut wtr = TableWriter::create(path, self.format).expect("Error when creating the file");
+
+        let headers: Vec<&str> = match self.by_user {
+            true => UserSummaries::HEADERS.to_vec(),
+            false => self.columns.iter().map(|c| c.name()).collect(),
+        };
+        wtr.headers(&headers)
+            .expect("Error when writing the header");
+
+        EnrollmentWriter {
+            wtr,
+            options: self,
+            users: match self.by_user {
+                true => Some(UserSummaries::default()),
+                false => None,
+            },
+        }
+    }
+}
+
+impl<'a> EnrollmentWriter<'a> {
+    /// Writes an enrollment, or keeps it until [`EnrollmentWriter::finish`]
+    /// in the per-user mode. Enrollments with roles that are not chosen are
+    /// skipped
+    pub fn write(&mut self, row: &Row) {
+        if !self.options.roles.matches(row.enrollment) {
+            return;
+        }
+
+        match &mut self.users {
+            Some(users) => users.add(row),
+            None => self
+                .wtr
+                .serialize(&Record {
+                    columns: &self.options.columns,
+                    row,
+                })
+                .expect("Error when writing a row"),
+        }
+    }
+
+    /// Writes the users (in the per-user mode) and completes the file
+    pub fn finish(mut self) {
+        if let Some(users) = self.users.take() {
+            for user in users.rows() {
+                self.wtr.serialize(&user).expect("Error when writing a row");
+            }
+        }
+
+        self.wtr.finish().expect("Error when writing the file");
+    }
+}
@@ -0,0 +1,190 @@
+use crate::canvas_api::Enrollment;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// What the role filters are compared with
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoleMatch {
+    /// The name of the role, including custom roles like "Course Responsible"
+    Name,
+
+    /// The base role (StudentEnrollment, TeacherEnrollment, TaEnrollment,
+    /// DesignerEnrollment or ObserverEnrollment), also for custom roles
+    Base,
+}
+
+/// Language of the role labels
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    En,
+    Sv,
+}
+
+/// Chooses the enrollments to write by their role
+#[derive(Debug)]
+pub struct RoleFilter {
+    /// Roles to write. If empty, all
+    pub include: Vec<String>,
+
+    /// Roles not to write
+    pub exclude: Vec<String>,
+    pub match_by: RoleMatch,
+}
+
+impl RoleFilter {
+    /// Returns `true` if the enrollment must be written
+    pub fn matches(&self, enrollment: &Enrollment) -> bool {
+        let role = match self.match_by {
+            RoleMatch::Name => &enrollment.role,
+            RoleMatch::Base => &enrollment.enrollment_type,
+        };
+
+        (self.include.is_empty() || self.include.iter().any(|r| same_role(r, role)))
+            && !self.exclude.iter().any(|r| same_role(r, role))
+    }
+}
+
+/// Label of a role, in Swedish and English
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Label {
+    en: String,
+    sv: String,
+}
+
+/// Human-readable labels of the Canvas roles. Custom roles can be added in a
+/// TOML file:
+///
+/// ```toml
+/// ["Course Responsible"]
+/// en = "Course responsible"
+/// sv = "Kursansvarig"
+/// ```
+#[derive(Debug)]
+pub struct RoleLabels {
+    labels: HashMap<String, Label>,
+}
+
+impl Default for RoleLabels {
+    /// Labels of the base roles
+    fn default() -> Self {
+        let labels = [
+            ("StudentEnrollment", "Student", "Student"),
+            ("TeacherEnrollment", "Teacher", "Lärare"),
+            ("TaEnrollment", "Teaching assistant", "Assistent"),
+            ("DesignerEnrollment", "Designer", "Designer"),
+            ("ObserverEnrollment", "Observer", "Observatör"),
+        ];
+
+        RoleLabels {
+            labels: labels
+                .iter()
+                .map(|(role, en, sv)| {
+                    let label = Label {
+                        en: en.to_string(),
+                        sv: sv.to_string(),
+                    };
+                    (role.to_string(), label)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RoleLabels {
+    /// Reads labels from a TOML file. The labels of the base roles are kept
+    /// unless the file changes them
+    pub fn load(path: &Path) -> Result<RoleLabels, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let mut labels = RoleLabels::default();
+
+        labels
+            .labels
+            .extend(toml::from_str::<HashMap<String, Label>>(&content)?);
+
+        Ok(labels)
+    }
+
+    /// Returns the label of the role of an enrollment, or the name of the
+    /// role if it has no label
+    pub fn label<'a>(&'a self, enrollment: &'a Enrollment, language: Language) -> &'a str {
+        match self.labels.get(&enrollment.role) {
+            None => &enrollment.role,
+            Some(label) => match language {
+                Language::En => &label.en,
+                Language::Sv => &label.sv,
+            },
+        }
+    }
+}
+
+/// Compares role names ignoring case and the `Enrollment` suffix, so
+/// `teacher` is the same as `TeacherEnrollment`
+fn same_role(a: &str, b: &str) -> bool {
+    let short = |role: &str| {
+        let role = role.to_lowercase();
+        role.strip_suffix("enrollment").unwrap_or(&role).to_string()
+    };
+
+    short(a) == short(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas_api;
+
+    fn enrollment(role: &str, enrollment_type: &str) -> Enrollment {
+        Enrollment {
+            role: role.to_string(),
+            enrollment_type: enrollment_type.to_string(),
+            ..canvas_api::enrollment(1)
+        }
+    }
+
+    #[test]
+    fn filters_roles() {
+        let responsible = enrollment("Course Responsible", "TeacherEnrollment");
+        let ta = enrollment("TaEnrollment", "TaEnrollment");
+
+        let teachers = RoleFilter {
+            include: vec!["Teacher".to_string()],
+            exclude: vec![],
+            match_by: RoleMatch::Base,
+        };
+        assert!(teachers.matches(&responsible));
+        assert!(!teachers.matches(&ta));
+
+        let no_responsibles = RoleFilter {
+            include: vec![],
+            exclude: vec!["course responsible".to_string()],
+            match_by: RoleMatch::Name,
+        };
+        assert!(!no_responsibles.matches(&responsible));
+        assert!(no_responsibles.matches(&ta));
+    }
+
+    #[test]
+    fn labels_roles() {
+        let path = std::env::temp_dir().join(format!("role-labels-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[\"Course Responsible\"]\nen = \"Course responsible\"\nsv = \"Kursansvarig\"\n",
+        )
+        .unwrap();
+        let labels = RoleLabels::load(&path);
+        fs::remove_file(&path).unwrap();
+        let labels = labels.unwrap();
+
+        let responsible = enrollment("Course Responsible", "TeacherEnrollment");
+        let teacher = enrollment("TeacherEnrollment", "TeacherEnrollment");
+        let custom = enrollment("Examiner", "TeacherEnrollment");
+
+        assert_eq!(labels.label(&responsible, Language::Sv), "Kursansvarig");
+        assert_eq!(labels.label(&teacher, Language::Sv), "Lärare");
+        assert_eq!(labels.label(&teacher, Language::En), "Teacher");
+        assert_eq!(labels.label(&custom, Language::En), "Examiner");
+    }
+}
@@ -0,0 +1,125 @@
+//! Typed Canvas Live Events
+//!
+//! Canvas sends every event as an envelope with `metadata` (name, time,
+//! account...) and a `body` that depends on the event. Only the events we
+//! react to are typed. The others are kept as [`Event::Other`].
+//!
+//! Canvas sends IDs as strings in Live Events.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// An event as sent by Canvas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub metadata: Metadata,
+    pub body: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub event_name: String,
+    pub event_time: Option<String>,
+    pub root_account_id: Option<String>,
+    pub user_id: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// Body of `enrollment_created` and `enrollment_updated`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EnrollmentEvent {
+    pub enrollment_id: String,
+    pub course_id: String,
+    pub course_section_id: Option<String>,
+    pub user_id: String,
+    pub user_name: Option<String>,
+
+    /// `StudentEnrollment`, `TeacherEnrollment` and so on
+    #[serde(rename = "type")]
+    pub enrollment_type: String,
+    pub workflow_state: String,
+    pub updated_at: Option<String>,
+}
+
+/// Body of `user_created`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct UserEvent {
+    pub user_id: String,
+    pub name: String,
+    pub short_name: Option<String>,
+    pub user_login: Option<String>,
+    pub user_sis_id: Option<String>,
+    pub workflow_state: String,
+}
+
+/// An event with a typed body
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    EnrollmentCreated(EnrollmentEvent),
+    EnrollmentUpdated(EnrollmentEvent),
+    UserCreated(UserEvent),
+
+    /// Any other event, with its name
+    Other(String),
+}
+
+impl Envelope {
+    /// Reads the body according to the name of the event
+    pub fn event(&self) -> Result<Event, serde_json::Error> {
+        let body = self.body.clone();
+
+        Ok(match self.metadata.event_name.as_str() {
+            "enrollment_created" => Event::EnrollmentCreated(serde_json::from_value(body)?),
+            "enrollment_updated" => Event::EnrollmentUpdated(serde_json::from_value(body)?),
+            "user_created" => Event::UserCreated(serde_json::from_value(body)?),
+            name => Event::Other(name.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_typed_events() {
+        let envelope: Envelope = serde_json::from_str(
+            r#"{
+                "metadata": {
+                    "event_name": "enrollment_created",
+                    "event_time": "2021-03-15T10:00:00.000Z",
+                    "root_account_id": "1"
+                },
+                "body": {
+                    "enrollment_id": "10",
+                    "course_id": "20",
+                    "course_section_id": "30",
+                    "user_id": "40",
+                    "user_name": "Ada Lovelace",
+                    "type": "StudentEnrollment",
+                    "workflow_state": "active"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        match envelope.event().unwrap() {
+            Event::EnrollmentCreated(enrollment) => {
+                assert_eq!(enrollment.user_id, "40");
+                assert_eq!(enrollment.enrollment_type, "StudentEnrollment");
+            }
+            other => panic!("Unexpected event {:?}", other),
+        }
+
+        let other = Envelope {
+            metadata: Metadata {
+                event_name: "logged_in".to_string(),
+                ..envelope.metadata
+            },
+            body: Value::Null,
+        };
+        assert_eq!(
+            other.event().unwrap(),
+            Event::Other("logged_in".to_string())
+        );
+    }
+}
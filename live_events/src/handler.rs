@@ -0,0 +1,105 @@
+//! What to do with the received events
+use crate::events::{Envelope, Event};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Something that reacts to events. Implement it to, for example, sync a
+/// section when an enrollment changes.
+pub trait Handler {
+    /// Handles an event. `event` is the typed version of `envelope`, or
+    /// `None` if its body could not be read. Such events are given to the
+    /// handlers anyway so they are not lost
+    fn handle(&mut self, envelope: &Envelope, event: Option<&Event>) -> Result<(), Box<dyn Error>>;
+}
+
+/// Appends every event to a file, one JSON object per line. Events whose
+/// body could not be read are also written
+pub struct LogHandler {
+    file: File,
+}
+
+impl LogHandler {
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<LogHandler> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(LogHandler { file })
+    }
+}
+
+impl Handler for LogHandler {
+    fn handle(
+        &mut self,
+        envelope: &Envelope,
+        _event: Option<&Event>,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(self.file, "{}", serde_json::to_string(envelope)?)?;
+        Ok(())
+    }
+}
+
+/// Prints a line for every event we know about
+pub struct PrintHandler;
+
+impl Handler for PrintHandler {
+    fn handle(
+        &mut self,
+        _envelope: &Envelope,
+        event: Option<&Event>,
+    ) -> Result<(), Box<dyn Error>> {
+        // The error in the body is already printed when receiving the event
+        let event = match event {
+            None => return Ok(()),
+            Some(event) => event,
+        };
+
+        match event {
+            Event::EnrollmentCreated(e) | Event::EnrollmentUpdated(e) => println!(
+                "Enrollment {} of user {} in section {}: {} ({})",
+                e.enrollment_id,
+                e.user_id,
+                e.course_section_id.as_deref().unwrap_or("-"),
+                e.enrollment_type,
+                e.workflow_state
+            ),
+            Event::UserCreated(u) => println!("User {} created: {}", u.user_id, u.name),
+            Event::Other(name) => println!("Ignoring event {}", name),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Metadata;
+    use serde_json::json;
+
+    #[test]
+    fn logs_events_without_type() {
+        let path = std::env::temp_dir().join(format!("live-events-{}.jsonl", std::process::id()));
+        let envelope = Envelope {
+            metadata: Metadata {
+                event_name: "enrollment_created".to_string(),
+                event_time: None,
+                root_account_id: None,
+                user_id: None,
+                request_id: None,
+            },
+            body: json!({"enrollment_id": 10}),
+        };
+        assert!(envelope.event().is_err());
+
+        LogHandler::open(&path)
+            .unwrap()
+            .handle(&envelope, None)
+            .unwrap();
+        let content = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let logged: Envelope = serde_json::from_str(content.unwrap().trim()).unwrap();
+        assert_eq!(logged, envelope);
+    }
+}
@@ -0,0 +1,105 @@
+mod events;
+mod handler;
+mod signature;
+
+use dotenv::dotenv;
+use events::Envelope;
+use handler::{Handler, LogHandler, PrintHandler};
+use signature::PayloadError;
+use std::env;
+use tiny_http::{Request, Response, Server};
+
+fn main() {
+    dotenv().ok();
+
+    let address = env::var("LIVE_EVENTS_ADDRESS").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+    let log_path = env::var("LIVE_EVENTS_LOG").unwrap_or_else(|_| "live-events.jsonl".to_string());
+    let secret = env("LIVE_EVENTS_SECRET");
+
+    let mut handlers: Vec<Box<dyn Handler>> = vec![
+        Box::new(LogHandler::open(&log_path).expect("Error when opening the log file")),
+        Box::new(PrintHandler),
+    ];
+
+    let server = Server::http(&address).expect("Error when starting the server");
+    println!("Listening on {}. Writing events to `{}`", address, log_path);
+
+    for mut request in server.incoming_requests() {
+        let status = match read_event(&mut request, secret.as_bytes()) {
+            Ok(envelope) => {
+                handle(&mut handlers, &envelope);
+                204
+            }
+            Err(PayloadError::Signature(e)) => {
+                println!("Rejected request: {}", e);
+                401
+            }
+            Err(PayloadError::Format(e)) => {
+                println!("Rejected request: {}", e);
+                400
+            }
+        };
+
+        if let Err(e) = request.respond(Response::empty(status)) {
+            println!("Error when responding: {}", e);
+        }
+    }
+}
+
+/// Reads and verifies the event in a request
+fn read_event(request: &mut Request, secret: &[u8]) -> Result<Envelope, PayloadError> {
+    let mut body = vec![];
+    request
+        .as_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| PayloadError::Format(e.to_string()))?;
+
+    if header(request, "Content-Type").as_deref() == Some("application/jwt") {
+        signature::verify_jwt(secret, &body)
+    } else {
+        signature::verify_hmac(secret, &body, header(request, "X-Signature").as_deref())
+    }
+}
+
+/// Gives the event to every handler, also if its body cannot be typed, so it
+/// is at least logged. Errors are printed: Canvas does not retry, so there is
+/// nothing else to do with them
+fn handle(handlers: &mut [Box<dyn Handler>], envelope: &Envelope) {
+    let event = match envelope.event() {
+        Ok(event) => Some(event),
+        Err(e) => {
+            println!(
+                "Cannot read the body of {}: {}",
+                envelope.metadata.event_name, e
+            );
+            None
+        }
+    };
+
+    for handler in handlers.iter_mut() {
+        if let Err(e) = handler.handle(envelope, event.as_ref()) {
+            println!(
+                "Error when handling {}: {}",
+                envelope.metadata.event_name, e
+            );
+        }
+    }
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn env(key: &str) -> String {
+    match env::var(key) {
+        Ok(val) => val,
+        Err(_) => {
+            println!("Environmental variable {} not defined", key);
+            panic!("Environmental variable {} not defined", key);
+        }
+    }
+}
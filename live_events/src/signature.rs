@@ -0,0 +1,139 @@
+//! Verification of signed payloads
+//!
+//! Two kinds of payloads are accepted, both signed with a shared secret:
+//!
+//! - A JSON envelope with an `X-Signature: sha256=<hex>` header that contains
+//!   the HMAC-SHA256 of the body
+//! - A JWT signed with HS256 whose claims are the envelope (sent by Canvas
+//!   with `Content-Type: application/jwt`)
+use crate::events::Envelope;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Errors when reading a payload
+#[derive(Debug)]
+pub enum PayloadError {
+    /// The payload has no signature or it is not valid
+    Signature(String),
+
+    /// The payload is signed but it is not a Live Event
+    Format(String),
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PayloadError::Signature(e) => write!(f, "Invalid signature: {}", e),
+            PayloadError::Format(e) => write!(f, "Invalid event: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
+
+/// Verifies a JSON payload signed with HMAC-SHA256. `signature` is the value
+/// of the `X-Signature` header
+pub fn verify_hmac(
+    secret: &[u8],
+    body: &[u8],
+    signature: Option<&str>,
+) -> Result<Envelope, PayloadError> {
+    let signature = signature
+        .ok_or_else(|| PayloadError::Signature("Missing X-Signature header".to_string()))?;
+    let hex = signature.strip_prefix("sha256=").ok_or_else(|| {
+        PayloadError::Signature("Only sha256 signatures are accepted".to_string())
+    })?;
+    let expected = decode_hex(hex)
+        .ok_or_else(|| PayloadError::Signature("Signature is not hexadecimal".to_string()))?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .map_err(|e| PayloadError::Signature(e.to_string()))?;
+    mac.update(body);
+    mac.verify_slice(&expected)
+        .map_err(|_| PayloadError::Signature("Signature does not match".to_string()))?;
+
+    serde_json::from_slice(body).map_err(|e| PayloadError::Format(e.to_string()))
+}
+
+/// Verifies a payload that is a JWT signed with HS256
+pub fn verify_jwt(secret: &[u8], body: &[u8]) -> Result<Envelope, PayloadError> {
+    let token = std::str::from_utf8(body)
+        .map_err(|e| PayloadError::Format(e.to_string()))?
+        .trim();
+
+    let mut validation = Validation::new(Algorithm::HS256);
+    // Events do not expire: they can be delivered late
+    validation.validate_exp = false;
+    validation.required_spec_claims = HashSet::new();
+
+    jsonwebtoken::decode::<Envelope>(token, &DecodingKey::from_secret(secret), &validation)
+        .map(|data| data.claims)
+        .map_err(|e| match e.kind() {
+            jsonwebtoken::errors::ErrorKind::Json(_) => PayloadError::Format(e.to_string()),
+            _ => PayloadError::Signature(e.to_string()),
+        })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    const BODY: &str = r#"{"metadata":{"event_name":"user_created"},"body":{"user_id":"1","name":"Ada","workflow_state":"registered"}}"#;
+
+    fn sign(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(body);
+        let hex: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        format!("sha256={}", hex)
+    }
+
+    #[test]
+    fn verifies_hmac_signatures() {
+        let signature = sign(b"secret", BODY.as_bytes());
+
+        let envelope = verify_hmac(b"secret", BODY.as_bytes(), Some(&signature)).unwrap();
+        assert_eq!(envelope.metadata.event_name, "user_created");
+
+        assert!(verify_hmac(b"other", BODY.as_bytes(), Some(&signature)).is_err());
+        assert!(verify_hmac(b"secret", BODY.as_bytes(), None).is_err());
+        assert!(verify_hmac(b"secret", b"{}", Some(&signature)).is_err());
+    }
+
+    #[test]
+    fn verifies_jwt_payloads() {
+        let envelope: Envelope = serde_json::from_str(BODY).unwrap();
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &envelope,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        assert_eq!(verify_jwt(b"secret", token.as_bytes()).unwrap(), envelope);
+        assert!(matches!(
+            verify_jwt(b"other", token.as_bytes()),
+            Err(PayloadError::Signature(_))
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+//! Writes rows (any struct that implements `Serialize`) to a file as CSV,
+//! JSON, NDJSON (one JSON object per line) or XLSX (Excel).
+//!
+//! The format is chosen explicitly or from the extension of the file:
+//!
+//! ```no_run
+//! use serde::Serialize;
+//! use table_output::TableWriter;
+//!
+//! #[derive(Serialize)]
+//! struct Row {
+//!     course_code: String,
+//!     count: i32,
+//! }
+//!
+//! let mut wtr = TableWriter::create("videos.xlsx", None).unwrap();
+//! wtr.serialize(&Row { course_code: "SF1624".to_string(), count: 3 }).unwrap();
+//! wtr.finish().unwrap();
+//! ```
+//!
+//! Every row is a table row and every field is a column. In CSV and XLSX, the
+//! header is the names of the fields of the first row (or the ones set with
+//! [`TableWriter::headers`]).
+use rust_xlsxwriter::{Workbook, Worksheet, XlsxError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Formats of the output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+
+    /// An array of objects
+    Json,
+
+    /// One object per line
+    Ndjson,
+    Xlsx,
+}
+
+impl Format {
+    /// Returns the format that corresponds to the extension of a file, if any
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Format> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        extension.parse().ok()
+    }
+
+    /// Returns the usual extension of the files in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Xlsx => "xlsx",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "ndjson" | "jsonl" => Ok(Format::Ndjson),
+            "xlsx" => Ok(Format::Xlsx),
+            _ => Err(format!(
+                "`{}` is not a format. Use csv, json, ndjson or xlsx",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Errors that can happen when writing a table
+#[derive(Debug)]
+pub enum OutputError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+    Xlsx(XlsxError),
+
+    /// The row is not a struct or a map, so it has no columns
+    NotARow(Value),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputError::Io(e) => write!(f, "Cannot write the file: {}", e),
+            OutputError::Csv(e) => write!(f, "Cannot write the CSV file: {}", e),
+            OutputError::Json(e) => write!(f, "Cannot write the JSON file: {}", e),
+            OutputError::Xlsx(e) => write!(f, "Cannot write the XLSX file: {}", e),
+            OutputError::NotARow(value) => write!(f, "{} is not a row with columns", value),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+impl From<io::Error> for OutputError {
+    fn from(e: io::Error) -> Self {
+        OutputError::Io(e)
+    }
+}
+
+impl From<csv::Error> for OutputError {
+    fn from(e: csv::Error) -> Self {
+        OutputError::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for OutputError {
+    fn from(e: serde_json::Error) -> Self {
+        OutputError::Json(e)
+    }
+}
+
+impl From<XlsxError> for OutputError {
+    fn from(e: XlsxError) -> Self {
+        OutputError::Xlsx(e)
+    }
+}
+
+enum Output {
+    Csv(Box<csv::Writer<File>>),
+    Json {
+        file: BufWriter<File>,
+        empty: bool,
+    },
+    Ndjson(BufWriter<File>),
+    Xlsx {
+        worksheet: Box<Worksheet>,
+        path: PathBuf,
+    },
+}
+
+/// Writes rows to a file. Call [`TableWriter::finish`] after the last row:
+/// JSON and XLSX files are not complete until then
+pub struct TableWriter {
+    output: Output,
+    headers: Option<Vec<String>>,
+
+    /// Rows written (without the header)
+    rows: u32,
+}
+
+impl TableWriter {
+    /// Creates the file in `path`. If `format` is `None`, it is chosen from
+    /// the extension of the file, or CSV if the extension is unknown
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        format: Option<Format>,
+    ) -> Result<TableWriter, OutputError> {
+        let path = path.as_ref();
+        let format = format
+            .or_else(|| Format::from_path(path))
+            .unwrap_or(Format::Csv);
+
+        let output = match format {
+            Format::Csv => Output::Csv(Box::new(csv::Writer::from_path(path)?)),
+            Format::Json => {
+                let mut file = BufWriter::new(File::create(path)?);
+                file.write_all(b"[")?;
+                Output::Json { file, empty: true }
+            }
+            Format::Ndjson => Output::Ndjson(BufWriter::new(File::create(path)?)),
+            Format::Xlsx => {
+                // Fail now, not after writing all the rows, if the file
+                // cannot be created
+                File::create(path)?;
+                Output::Xlsx {
+                    worksheet: Box::new(Worksheet::new()),
+                    path: path.to_path_buf(),
+                }
+            }
+        };
+
+        Ok(TableWriter {
+            output,
+            headers: None,
+            rows: 0,
+        })
+    }
+
+    /// Writes the header of CSV and XLSX files before the first row. Use it
+    /// so the header is written even if there are no rows. It does nothing
+    /// in JSON and NDJSON files.
+    pub fn headers<S: AsRef<str>>(&mut self, headers: &[S]) -> Result<(), OutputError> {
+        let headers: Vec<String> = headers.iter().map(|h| h.as_ref().to_string()).collect();
+
+        match &mut self.output {
+            Output::Csv(wtr) => wtr.write_record(&headers)?,
+            Output::Xlsx { worksheet, .. } => {
+                let bold = rust_xlsxwriter::Format::new().set_bold();
+
+                for (column, header) in headers.iter().enumerate() {
+                    worksheet.write_string_with_format(0, column as u16, header, &bold)?;
+                }
+            }
+            Output::Json { .. } | Output::Ndjson(_) => {}
+        }
+
+        self.headers = Some(headers);
+        Ok(())
+    }
+
+    /// Writes a row
+    pub fn serialize<T: Serialize>(&mut self, row: &T) -> Result<(), OutputError> {
+        let row = match serde_json::to_value(row)? {
+            Value::Object(row) => row,
+            other => return Err(OutputError::NotARow(other)),
+        };
+
+        if self.headers.is_none() {
+            let headers: Vec<String> = row.keys().cloned().collect();
+            self.headers(&headers)?;
+        }
+
+        match &mut self.output {
+            Output::Csv(wtr) => wtr.write_record(row.values().map(to_text))?,
+            Output::Json { file, empty } => {
+                if !*empty {
+                    file.write_all(b",")?;
+                }
+                file.write_all(b"\n")?;
+                serde_json::to_writer(&mut *file, &row)?;
+                *empty = false;
+            }
+            Output::Ndjson(file) => {
+                serde_json::to_writer(&mut *file, &row)?;
+                file.write_all(b"\n")?;
+            }
+            Output::Xlsx { worksheet, .. } => write_cells(worksheet, self.rows + 1, &row)?,
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Completes and closes the file
+    pub fn finish(self) -> Result<(), OutputError> {
+        match self.output {
+            Output::Csv(mut wtr) => wtr.flush()?,
+            Output::Json { mut file, empty } => {
+                file.write_all(if empty { b"]\n" } else { b"\n]\n" })?;
+                file.flush()?;
+            }
+            Output::Ndjson(mut file) => file.flush()?,
+            Output::Xlsx {
+                mut worksheet,
+                path,
+            } => {
+                worksheet.autofit();
+                let mut workbook = Workbook::new();
+                workbook.push_worksheet(*worksheet);
+                workbook.save(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the value of a cell in a CSV file. `null` is an empty cell
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes a row of an XLSX file. Numbers and booleans keep their type
+fn write_cells(
+    worksheet: &mut Worksheet,
+    row: u32,
+    values: &Map<String, Value>,
+) -> Result<(), XlsxError> {
+    for (column, value) in values.values().enumerate() {
+        let column = column as u16;
+
+        match value {
+            Value::Null => {}
+            Value::Bool(b) => {
+                worksheet.write_boolean(row, column, *b)?;
+            }
+            Value::Number(n) => match n.as_f64() {
+                Some(n) => {
+                    worksheet.write_number(row, column, n)?;
+                }
+                None => {
+                    worksheet.write_string(row, column, n.to_string())?;
+                }
+            },
+            other => {
+                worksheet.write_string(row, column, to_text(other))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[derive(Serialize)]
+    struct Row {
+        course_code: &'static str,
+        count: i32,
+        title: Option<&'static str>,
+    }
+
+    fn write(file_name: &str, format: Option<Format>) -> PathBuf {
+        let path =
+            env::temp_dir().join(format!("table-output-{}-{}", std::process::id(), file_name));
+        let mut wtr = TableWriter::create(&path, format).unwrap();
+
+        for row in &[
+            Row {
+                course_code: "SF1624",
+                count: 3,
+                title: Some("Algebra, \"linjär\""),
+            },
+            Row {
+                course_code: "DD1337",
+                count: 0,
+                title: None,
+            },
+        ] {
+            wtr.serialize(row).unwrap();
+        }
+
+        wtr.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn writes_every_format() {
+        let csv = write("rows.csv", None);
+        assert_eq!(
+            fs::read_to_string(&csv).unwrap(),
+            "course_code,count,title\nSF1624,3,\"Algebra, \"\"linjär\"\"\"\nDD1337,0,\n"
+        );
+
+        let json = write("rows.json", None);
+        let rows: Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+        assert_eq!(rows[1]["course_code"], "DD1337");
+        assert_eq!(rows[1]["title"], Value::Null);
+
+        let ndjson = write("rows.txt", Some(Format::Ndjson));
+        let text = fs::read_to_string(&ndjson).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.starts_with(r#"{"course_code":"SF1624","count":3"#));
+
+        // XLSX files are zip files
+        let xlsx = write("rows.xlsx", None);
+        assert!(fs::read(&xlsx).unwrap().starts_with(b"PK"));
+
+        for path in &[csv, json, ndjson, xlsx] {
+            fs::remove_file(path).ok();
+        }
+    }
+
+    #[test]
+    fn chooses_format_from_extension() {
+        assert_eq!(Format::from_path("a/b.XLSX"), Some(Format::Xlsx));
+        assert_eq!(Format::from_path("b.jsonl"), Some(Format::Ndjson));
+        assert_eq!(Format::from_path("enrollments"), None);
+        assert!("pdf".parse::<Format>().is_err());
+    }
+}
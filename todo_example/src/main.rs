@@ -1,15 +1,16 @@
 //! This is `todo_example`, a program that reads a list of TODOs from an API
-//! and writes that to a CSV file.
+//! and writes that to a CSV file (or JSON, NDJSON or XLSX, depending on the
+//! extension of the file name).
 //!
 //! It uses three libraries:
 //! - `reqwest` to fetch data from the API
-//! - `csv` to write data to the CSV file
+//! - `table_output` (in this repository) to write data to the file
 //! - `serde` to make the conversion between JSON string to Rust objects and
-//!   from Rust objects to CSV string
-use csv::Writer;
+//!   from Rust objects to rows of the file
 use dialoguer::{theme::ColorfulTheme, Input, Select};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use table_output::TableWriter;
 
 /// Prompts to the user if they want to see a list of completed TODOs,
 /// not-completed TODOs or all.
@@ -34,7 +35,8 @@ fn prompt_completed() -> Option<bool> {
     }
 }
 
-/// Prompt a file-name
+/// Prompt a file-name. The extension (`.csv`, `.json`, `.ndjson` or `.xlsx`)
+/// chooses the format of the file
 fn prompt_filename() -> String {
     Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Write a filename")
@@ -75,8 +77,9 @@ fn fetch_todos(completed_filter: Option<bool>) -> Vec<Todo> {
     }
 }
 
-/// Structure of the CSV row that we are going to write. Implements the
-/// `Serialize` so that `serde` can convert the struct itself into a CSV string
+/// Structure of the row that we are going to write. Implements the
+/// `Serialize` so that `serde` can convert the struct itself into a row of the
+/// file
 #[derive(Serialize)]
 struct Row<'a> {
     id: i32,
@@ -85,12 +88,12 @@ struct Row<'a> {
     completed: bool,
 }
 
-/// Write TODOs into a CSV file
+/// Write TODOs into a file
 fn write_todos(todos: Vec<Todo>, filename: String) {
-    let mut wtr = Writer::from_path(filename).expect("Error when creating the writer");
+    let mut wtr = TableWriter::create(filename, None).expect("Error when creating the writer");
 
     for todo in todos.iter() {
-        wtr.serialize(Row {
+        wtr.serialize(&Row {
             id: todo.id,
             user_id: todo.user_id,
             title: &todo.title,
@@ -98,7 +101,7 @@ fn write_todos(todos: Vec<Todo>, filename: String) {
         })
         .expect("Error when writing a row");
     }
-    wtr.flush().expect("Error when flushing the writer");
+    wtr.finish().expect("Error when finishing the file");
 }
 
 fn main() {
@@ -35,7 +35,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     for result in Reader::from_path("provisioning.csv")?.deserialize() {
         let user: User = result?;
 
-        if user.user_id == None && user.email != None {
+        if user.user_id.is_none() && user.email.is_some() {
             let n = all_rows
                 .iter()
                 .filter(|r| r.canvas_user_id == user.canvas_user_id)